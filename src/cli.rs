@@ -0,0 +1,337 @@
+//! Subcommands for inspecting and managing synthehol state through the
+//! sqlite database shared with the running monitors.
+//!
+use clap::Subcommand;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::db::SynthDb;
+use crate::incident::{Acknowledgement, IncidentEvent};
+use crate::matcher::MonitorMatcher;
+use crate::silence::Silence;
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Manage silences that suppress reporting during maintenance
+    Silence {
+        #[command(subcommand)]
+        action: SilenceCommand,
+    },
+    /// List recent level transitions
+    Transitions {
+        /// monitor name, may include '*' and '?' wildcards
+        #[clap(long)]
+        monitor: Option<String>,
+        /// only include transitions after this time (RFC 3339)
+        #[clap(long)]
+        since: Option<String>,
+        #[clap(long, default_value_t = 50)]
+        limit: u64,
+    },
+    /// Inspect open and recent incidents
+    Incidents {
+        #[command(subcommand)]
+        action: IncidentCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SilenceCommand {
+    /// Silence monitors matching a name pattern and/or tag
+    Add {
+        /// monitor name, may include '*' and '?' wildcards
+        #[clap(long)]
+        monitor: Option<String>,
+        /// monitor tag, may include '*' and '?' wildcards
+        #[clap(long)]
+        tag: Option<String>,
+        /// start time (RFC 3339), defaults to now
+        #[clap(long)]
+        start: Option<String>,
+        /// end time (RFC 3339)
+        #[clap(long, required_unless_present = "duration")]
+        end: Option<String>,
+        /// length of the silence from the start time (e.g. "2h")
+        #[clap(long, conflicts_with = "end")]
+        duration: Option<String>,
+        /// defaults to $USER
+        #[clap(long)]
+        creator: Option<String>,
+        #[clap(long, default_value = "")]
+        comment: String,
+    },
+    /// List active and upcoming silences
+    List {
+        /// include silences that have already ended
+        #[clap(long)]
+        all: bool,
+    },
+    /// Remove a silence by id
+    Remove { id: i64 },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IncidentCommand {
+    /// List the most recent incidents
+    List {
+        /// only include incidents that haven't cleared
+        #[clap(long)]
+        open: bool,
+        #[clap(long, default_value_t = 20)]
+        limit: u64,
+    },
+    /// Show the history of an incident
+    Show { id: i64 },
+    /// Acknowledge an open incident, holding escalation and notifications
+    Ack {
+        id: i64,
+        /// how long the acknowledgement lasts (e.g. "2h"), defaults to
+        /// until the incident clears
+        #[clap(long)]
+        duration: Option<String>,
+        /// defaults to $USER
+        #[clap(long)]
+        by: Option<String>,
+        #[clap(long, default_value = "")]
+        comment: String,
+    },
+    /// Remove the acknowledgement of an incident
+    Unack { id: i64 },
+}
+
+pub async fn run(cmd: Command, db_path: &str) -> Result<(), String> {
+    let db = SynthDb::new(Some(db_path))
+        .await
+        .map_err(|e| format!("failed to open sqlite database ({})", e))?;
+    db.initialize_db()
+        .await
+        .map_err(|e| format!("failed to initialize database ({})", e))?;
+    match cmd {
+        Command::Silence { action } => silence(action, &db).await,
+        Command::Incidents { action } => incidents(action, &db).await,
+        Command::Transitions {
+            monitor,
+            since,
+            limit,
+        } => {
+            let since = match since {
+                Some(s) => parse_time(&s)?,
+                None => 0,
+            };
+            let transitions = db
+                .get_transitions(monitor, since, limit)
+                .await
+                .map_err(|e| format!("failed to list transitions ({})", e))?;
+            for t in transitions {
+                println!(
+                    "{}\t{}\t{} -> {}\t{}\tresult={}",
+                    format_time(t.time),
+                    t.monitor,
+                    t.from_level,
+                    t.to_level,
+                    t.reason,
+                    t.result_id.map(|id| id.to_string()).unwrap_or_default()
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn silence(cmd: SilenceCommand, db: &SynthDb) -> Result<(), String> {
+    match cmd {
+        SilenceCommand::Add {
+            monitor,
+            tag,
+            start,
+            end,
+            duration,
+            creator,
+            comment,
+        } => {
+            let matcher = MonitorMatcher { monitor, tag };
+            if matcher.is_empty() {
+                return Err(String::from("either --monitor or --tag is required"));
+            }
+            let start_time = match start {
+                Some(s) => parse_time(&s)?,
+                None => now(),
+            };
+            let end_time = match (end, duration) {
+                (Some(e), _) => parse_time(&e)?,
+                (None, Some(d)) => {
+                    let d = humantime::parse_duration(&d)
+                        .map_err(|e| format!("invalid duration \"{}\" ({})", d, e))?;
+                    start_time + d.as_millis() as u64
+                }
+                (None, None) => return Err(String::from("either --end or --duration is required")),
+            };
+            if end_time <= start_time {
+                return Err(String::from("silence must end after it starts"));
+            }
+            let silence = Silence {
+                id: 0,
+                matcher,
+                start_time,
+                end_time,
+                creator: creator.or(std::env::var("USER").ok()).unwrap_or_default(),
+                comment,
+            };
+            let id = db
+                .add_silence(silence)
+                .await
+                .map_err(|e| format!("failed to add silence ({})", e))?;
+            println!("added silence {}", id);
+        }
+        SilenceCommand::List { all } => {
+            let silences = db
+                .get_silences(if all { None } else { Some(now()) })
+                .await
+                .map_err(|e| format!("failed to list silences ({})", e))?;
+            for s in silences {
+                println!(
+                    "{}\tmonitor={}\ttag={}\t{} - {}\t{}\t{}",
+                    s.id,
+                    s.matcher.monitor.unwrap_or(String::from("*")),
+                    s.matcher.tag.unwrap_or(String::from("*")),
+                    format_time(s.start_time),
+                    format_time(s.end_time),
+                    s.creator,
+                    s.comment
+                );
+            }
+        }
+        SilenceCommand::Remove { id } => {
+            let removed = db
+                .remove_silence(id)
+                .await
+                .map_err(|e| format!("failed to remove silence ({})", e))?;
+            if !removed {
+                return Err(format!("no silence with id {}", id));
+            }
+            println!("removed silence {}", id);
+        }
+    }
+    Ok(())
+}
+
+async fn incidents(cmd: IncidentCommand, db: &SynthDb) -> Result<(), String> {
+    match cmd {
+        IncidentCommand::List { open, limit } => {
+            let incidents = db
+                .get_incidents(open, limit)
+                .await
+                .map_err(|e| format!("failed to list incidents ({})", e))?;
+            for i in incidents {
+                let mut status = i.resolution.unwrap_or(String::from("open"));
+                if let Some(a) = i
+                    .ack
+                    .filter(|a| i.cleared_at.is_none() && a.is_active(now()))
+                {
+                    status = format!("acknowledged by {}", a.by);
+                }
+                println!(
+                    "{}\t{}\tpeak={}\t{} - {}\t{}",
+                    i.id,
+                    i.monitor,
+                    i.peak_level,
+                    format_time(i.opened_at),
+                    i.cleared_at.map(format_time).unwrap_or_default(),
+                    status
+                );
+            }
+        }
+        IncidentCommand::Show { id } => {
+            let events = db
+                .get_incident_events(id)
+                .await
+                .map_err(|e| format!("failed to get incident ({})", e))?;
+            if events.is_empty() {
+                return Err(format!("no incident with id {}", id));
+            }
+            for e in events {
+                println!(
+                    "{}\t{}\tlevel={}\t{}",
+                    format_time(e.time),
+                    e.kind,
+                    e.level,
+                    e.detail
+                );
+            }
+        }
+        IncidentCommand::Ack {
+            id,
+            duration,
+            by,
+            comment,
+        } => {
+            let time = now();
+            let expires_at = match duration {
+                Some(d) => {
+                    let d = humantime::parse_duration(&d)
+                        .map_err(|e| format!("invalid duration \"{}\" ({})", d, e))?;
+                    Some(time + d.as_millis() as u64)
+                }
+                None => None,
+            };
+            let ack = Acknowledgement {
+                time,
+                expires_at,
+                by: by.or(std::env::var("USER").ok()).unwrap_or_default(),
+                comment,
+            };
+            let detail = match expires_at {
+                Some(e) => format!("by {} until {} {}", ack.by, format_time(e), ack.comment),
+                None => format!("by {} {}", ack.by, ack.comment),
+            };
+            let acked = db
+                .acknowledge_incident(id, ack)
+                .await
+                .map_err(|e| format!("failed to acknowledge incident ({})", e))?;
+            if !acked {
+                return Err(format!("no open incident with id {}", id));
+            }
+            let event = IncidentEvent::new(time, "acknowledged", "", detail.trim().to_string());
+            db.add_incident_event(id, event, 0)
+                .await
+                .map_err(|e| format!("failed to record acknowledgement ({})", e))?;
+            println!("acknowledged incident {}", id);
+        }
+        IncidentCommand::Unack { id } => {
+            let removed = db
+                .unacknowledge_incident(id)
+                .await
+                .map_err(|e| format!("failed to remove acknowledgement ({})", e))?;
+            if !removed {
+                return Err(format!("incident {} isn't acknowledged", id));
+            }
+            let event = IncidentEvent::new(now(), "unacknowledged", "", String::new());
+            db.add_incident_event(id, event, 0)
+                .await
+                .map_err(|e| format!("failed to record acknowledgement ({})", e))?;
+            println!("removed acknowledgement of incident {}", id);
+        }
+    }
+    Ok(())
+}
+
+/// current time in ms since epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time error")
+        .as_millis() as u64
+}
+
+/// parse an RFC 3339 timestamp into ms since epoch
+fn parse_time(t: &str) -> Result<u64, String> {
+    humantime::parse_rfc3339_weak(t)
+        .map_err(|e| format!("invalid time \"{}\" ({})", t, e))?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .map_err(|e| format!("invalid time \"{}\" ({})", t, e))
+}
+
+/// format ms since epoch as an RFC 3339 timestamp
+fn format_time(t: u64) -> String {
+    humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_millis(t)).to_string()
+}
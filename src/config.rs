@@ -1,22 +1,36 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 use std::path::Path;
+use std::time::Duration;
 use std::{fmt, fs, io};
 
+use crate::dispatch::DispatchArgs;
+use crate::group::MonitorGroupArgs;
 use crate::monitor::MonitorArgs;
-use crate::reporters::pagerduty::PagerdutyReporterArgs;
-use crate::reporters::postgresql::PostgresqlReporterArgs;
-use crate::reporters::slack::SlackReporterArgs;
-use crate::reporters::splunk::SplunkReporterArgs;
+use crate::reporters::ReporterArgs;
+use crate::silence::SilenceWindowArgs;
 
 #[derive(Deserialize, Debug, Default)]
 pub struct Config {
     pub log_level: Option<String>,
     pub use_db_persistence: Option<bool>,
+    pub max_concurrent_targets: Option<usize>,
+    pub concurrency_group: Option<Vec<ConcurrencyGroupArgs>>,
     pub monitor: Option<Vec<MonitorArgs>>,
-    pub splunk: Option<SplunkReporterArgs>,
-    pub slack: Option<SlackReporterArgs>,
-    pub pagerduty: Option<PagerdutyReporterArgs>,
-    pub postgresql: Option<PostgresqlReporterArgs>,
+    pub monitor_group: Option<Vec<MonitorGroupArgs>>,
+    pub silence: Option<Vec<SilenceWindowArgs>>,
+    pub dispatch: Option<DispatchArgs>,
+    pub reporter: Option<Vec<ReporterArgs>>,
+    /// single reporter sections such as [slack], keyed by reporter type
+    #[serde(flatten)]
+    pub reporter_sections: toml::Table,
+}
+
+/// A named limit on how many monitor targets assigned to the
+/// group may execute at the same time
+#[derive(Clone, Deserialize, Debug)]
+pub struct ConcurrencyGroupArgs {
+    pub name: String,
+    pub max_concurrent: usize,
 }
 
 impl Config {
@@ -24,13 +38,59 @@ impl Config {
         Config {
             log_level: None,
             use_db_persistence: None,
+            max_concurrent_targets: None,
+            concurrency_group: None,
             monitor: Some(Vec::new()),
-            splunk: None,
-            slack: None,
-            pagerduty: None,
-            postgresql: None,
+            monitor_group: None,
+            silence: None,
+            dispatch: None,
+            reporter: None,
+            reporter_sections: toml::Table::new(),
         }
     }
+    /// All configured reporter instances, single reporter sections
+    /// are named after their type
+    pub fn reporters(&self) -> Result<Vec<ReporterArgs>, String> {
+        let mut reporters = Vec::new();
+        // unknown top-level keys land here too, so anything that isn't
+        // a section is most likely a misspelt setting
+        for (kind, args) in self.reporter_sections.iter() {
+            let toml::Value::Table(args) = args else {
+                return Err(format!("unknown configuration key {}", kind));
+            };
+            reporters.push(ReporterArgs::new(kind, kind, args.clone()));
+        }
+        for r in self.reporter.clone().unwrap_or_default() {
+            if reporters
+                .iter()
+                .any(|x| x.name.eq_ignore_ascii_case(&r.name))
+            {
+                return Err(format!("duplicate reporter name {}", r.name));
+            }
+            reporters.push(r);
+        }
+        Ok(reporters)
+    }
+
+    /// check settings that would otherwise stall every monitor at runtime
+    fn validate(&self) -> Result<(), ConfigError> {
+        // a limit with no permits would block every target forever
+        if self.max_concurrent_targets == Some(0) {
+            return Err(ConfigError::Invalid(String::from(
+                "max_concurrent_targets must be at least 1",
+            )));
+        }
+        for g in self.concurrency_group.iter().flatten() {
+            if g.max_concurrent == 0 {
+                return Err(ConfigError::Invalid(format!(
+                    "concurrency group {} max_concurrent must be at least 1",
+                    g.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
     fn overlay(&mut self, other: &Self) {
         // could probably make this a lot cleaner with a macro
         // check each top-level config stanza, if set in other,
@@ -44,21 +104,33 @@ impl Config {
             // dbg!("setting use_db_persistence config overlay");
             self.use_db_persistence = Some(v)
         }
-        if let Some(v) = other.splunk.clone() {
-            // dbg!("setting splunk reporter config overlay");
-            self.splunk = Some(v)
+        if let Some(v) = other.max_concurrent_targets {
+            // dbg!("setting max_concurrent_targets config overlay");
+            self.max_concurrent_targets = Some(v)
+        }
+        if let Some(v) = other.concurrency_group.clone() {
+            // dbg!("setting concurrency_group config overlay");
+            self.concurrency_group = Some(v)
         }
-        if let Some(v) = other.slack.clone() {
-            // dbg!("setting slack reporter config overlay");
-            self.slack = Some(v)
+        if let Some(v) = other.monitor_group.clone() {
+            // dbg!("setting monitor_group config overlay");
+            self.monitor_group = Some(v)
         }
-        if let Some(v) = other.pagerduty.clone() {
-            // dbg!("setting pagerduty reporter config overlay");
-            self.pagerduty = Some(v)
+        if let Some(v) = other.silence.clone() {
+            // dbg!("setting silence config overlay");
+            self.silence = Some(v)
         }
-        if let Some(v) = other.postgresql.clone() {
-            // dbg!("setting postgresql reporter config overlay");
-            self.postgresql = Some(v)
+        if let Some(v) = other.dispatch.clone() {
+            // dbg!("setting dispatch config overlay");
+            self.dispatch = Some(v)
+        }
+        if let Some(v) = other.reporter.clone() {
+            // dbg!("setting reporter config overlay");
+            self.reporter = Some(v)
+        }
+        for (k, v) in other.reporter_sections.iter() {
+            // dbg!("setting {} reporter config overlay", k);
+            self.reporter_sections.insert(k.clone(), v.clone());
         }
         // for monitors, we need to merge the Vecs based
         // on monitor name
@@ -117,12 +189,49 @@ pub fn parse_config(p: &str) -> Result<Config, ConfigError> {
     } else {
         return Err(ConfigError::InvalidPath(p.to_string()));
     }
+    config.validate()?;
     Ok(config)
 }
 
+/// Durations can be given as an integer number of seconds, or as a
+/// humantime string such as "500ms", "2m" or "1h30m"
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DurationArg {
+    Seconds(u64),
+    Human(String),
+}
+
+impl DurationArg {
+    fn into_duration<E: serde::de::Error>(self) -> Result<Duration, E> {
+        match self {
+            DurationArg::Seconds(s) => Ok(Duration::from_secs(s)),
+            DurationArg::Human(s) => humantime::parse_duration(&s)
+                .map_err(|e| E::custom(format!("invalid duration \"{}\" ({})", s, e))),
+        }
+    }
+}
+
+pub fn deserialize_duration<'de, D>(d: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    DurationArg::deserialize(d)?.into_duration()
+}
+
+pub fn deserialize_opt_duration<'de, D>(d: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<DurationArg>::deserialize(d)?
+        .map(|v| v.into_duration())
+        .transpose()
+}
+
 #[derive(Debug)]
 pub enum ConfigError {
     InvalidPath(String),
+    Invalid(String),
     Io(io::Error),
     Toml(toml::de::Error),
 }
@@ -131,6 +240,7 @@ impl fmt::Display for ConfigError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ConfigError::InvalidPath(e) => write!(f, "invalid config path provided ({})", e),
+            ConfigError::Invalid(e) => write!(f, "invalid configuration ({})", e),
             ConfigError::Io(e) => write!(f, "IO error encountered ({})", e),
             ConfigError::Toml(e) => write!(f, "Toml error encountered ({})", e),
         }
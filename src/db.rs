@@ -1,12 +1,58 @@
 use rusqlite::params;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use tokio_rusqlite::Connection;
 use tracing::{debug, error, instrument};
 
-use crate::monitor::MonitorResult;
+use crate::incident::{Acknowledgement, Incident, IncidentEvent};
+use crate::matcher::{glob_match, MonitorMatcher};
+use crate::monitor::{MonitorResult, Transition};
+use crate::silence::Silence;
+
+/// results are pruned after this many inserts rather than on every
+/// insert, so a monitor may briefly hold more than it retains
+const PRUNE_EVERY: u64 = 100;
+/// results kept for each monitor, more are kept for a monitor whose
+/// windowed escalation rules need a longer history
+const RESULTS_RETAINED: u64 = 500;
+/// level transitions are kept far longer than results, pruned by age
+const TRANSITIONS_RETAINED_MS: u64 = 365 * 24 * 60 * 60 * 1000;
+
+/// a database that persists nothing, used by monitors until one is registered
+pub static NO_DB: SynthDb = SynthDb {
+    db: None,
+    results_since_prune: AtomicU64::new(0),
+    retention: Mutex::new(BTreeMap::new()),
+};
+
+/// Persisted state of a monitor, restored on restart
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MonitorState {
+    pub level_index: usize,
+    pub failure_tally: u64,
+    pub success_tally: u64,
+    /// start of the current failure or success streak (ms)
+    pub streak_start: u64,
+    pub flapping: bool,
+    /// reporters notified during the current incident, cleared on recovery
+    pub notified: Vec<String>,
+    /// the open incident, if the monitor is escalated
+    pub incident_id: Option<i64>,
+    /// the open incident has an active acknowledgement
+    pub acknowledged: bool,
+    /// the clear of a recovered incident is held until the monitor is
+    /// no longer silenced or flapping
+    pub clear_pending: bool,
+}
 
 #[derive(Debug)]
 pub struct SynthDb {
     pub db: Option<&'static tokio_rusqlite::Connection>,
+    /// inserts since the last prune, shared by all monitors using the db
+    results_since_prune: AtomicU64,
+    /// results each monitor needs kept, as a number of runs and an age (ms)
+    retention: Mutex<BTreeMap<String, (u64, u64)>>,
 }
 
 impl SynthDb {
@@ -18,7 +64,21 @@ impl SynthDb {
 
         let db = Box::leak(Box::new(db));
 
-        Ok(SynthDb { db: Some(db) })
+        Ok(SynthDb {
+            db: Some(db),
+            results_since_prune: AtomicU64::new(0),
+            retention: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Set the history a monitor needs kept across restarts, at least the
+    /// given number of runs and every run within the age (ms), on top of
+    /// the RESULTS_RETAINED kept for every monitor
+    pub fn set_retention(&self, monitor: &str, runs: u64, age: u64) {
+        self.retention
+            .lock()
+            .expect("retention lock poisoned")
+            .insert(monitor.to_string(), (runs, age));
     }
 
     #[instrument]
@@ -27,7 +87,13 @@ impl SynthDb {
         debug!("setting sqlite pragmas...");
         if let Some(db) = self.db {
             db.call(|db| {
-                db.execute("PRAGMA cache_size = -4096", [])
+                db.execute("PRAGMA cache_size = -4096", [])?;
+                // WAL with relaxed syncing keeps the per-cycle writes cheap
+                // enough for sub-second monitor intervals
+                db.query_row("PRAGMA journal_mode = WAL", [], |row| {
+                    row.get::<_, String>(0)
+                })?;
+                db.execute("PRAGMA synchronous = NORMAL", [])
                     .map_err(|e| e.into())
             })
             .await
@@ -51,7 +117,12 @@ impl SynthDb {
                         stdout TEXT,
                         stderr TEXT,
                         duration INTEGER,
-                        status INTEGER
+                        queue_time INTEGER,
+                        status INTEGER,
+                        skipped INTEGER NOT NULL DEFAULT 0,
+                        silenced INTEGER NOT NULL DEFAULT 0,
+                        incident_id INTEGER,
+                        replica INTEGER
                     )",
                     [],
                 )
@@ -60,6 +131,28 @@ impl SynthDb {
             .await?;
         }
 
+        // columns added to results after the initial release
+        self.add_column("results", "queue_time", "INTEGER").await?;
+        self.add_column("results", "skipped", "INTEGER NOT NULL DEFAULT 0")
+            .await?;
+        self.add_column("results", "silenced", "INTEGER NOT NULL DEFAULT 0")
+            .await?;
+        self.add_column("results", "incident_id", "INTEGER").await?;
+        self.add_column("results", "replica", "INTEGER").await?;
+
+        // index for per-monitor result history lookups
+        if let Some(db) = self.db {
+            db.call(|db| {
+                db.execute(
+                    "CREATE INDEX IF NOT EXISTS idx_results_monitor_name_id
+                        ON results (monitor_name, id);",
+                    [],
+                )
+                .map_err(|e| e.into())
+            })
+            .await?;
+        }
+
         // create monitor_state table if it doesn't exist
         debug!("attempting to create monitor_state table...");
         if let Some(db) = self.db {
@@ -70,7 +163,13 @@ impl SynthDb {
                         name  TEXT UNIQUE,
                         level_index INTEGER NOT NULL,
                         failure_tally INTEGER NOT NULL,
-                        success_tally INTEGER NOT NULL
+                        success_tally INTEGER NOT NULL,
+                        streak_start INTEGER NOT NULL DEFAULT 0,
+                        flapping INTEGER NOT NULL DEFAULT 0,
+                        notified TEXT NOT NULL DEFAULT '[]',
+                        incident_id INTEGER,
+                        acknowledged INTEGER NOT NULL DEFAULT 0,
+                        clear_pending INTEGER NOT NULL DEFAULT 0
                     )",
                     [],
                 )
@@ -79,6 +178,32 @@ impl SynthDb {
             .await?;
         }
 
+        // columns added to monitor_state after the initial release
+        self.add_column(
+            "monitor_state",
+            "streak_start",
+            "INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+        self.add_column("monitor_state", "flapping", "INTEGER NOT NULL DEFAULT 0")
+            .await?;
+        self.add_column("monitor_state", "notified", "TEXT NOT NULL DEFAULT '[]'")
+            .await?;
+        self.add_column("monitor_state", "incident_id", "INTEGER")
+            .await?;
+        self.add_column(
+            "monitor_state",
+            "acknowledged",
+            "INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+        self.add_column(
+            "monitor_state",
+            "clear_pending",
+            "INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+
         // create reporter_state table if it doesn't exist
         debug!("attempting to create reporter_state table...");
         if let Some(db) = self.db {
@@ -109,11 +234,125 @@ impl SynthDb {
             })
             .await?;
         }
+
+        // create silences table if it doesn't exist
+        debug!("attempting to create silences table...");
+        if let Some(db) = self.db {
+            db.call(|db| {
+                db.execute(
+                    "CREATE TABLE IF NOT EXISTS silences (
+                        id INTEGER PRIMARY KEY,
+                        monitor TEXT,
+                        tag TEXT,
+                        start_time INTEGER NOT NULL,
+                        end_time INTEGER NOT NULL,
+                        creator TEXT NOT NULL,
+                        comment TEXT NOT NULL
+                    )",
+                    [],
+                )
+                .map_err(|e| e.into())
+            })
+            .await?;
+        }
+
+        // create transitions table if it doesn't exist, kept separately
+        // from results so level history outlives result pruning
+        debug!("attempting to create transitions table...");
+        if let Some(db) = self.db {
+            db.call(|db| {
+                db.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS transitions (
+                        id INTEGER PRIMARY KEY,
+                        monitor TEXT NOT NULL,
+                        time INTEGER NOT NULL,
+                        from_level TEXT NOT NULL,
+                        to_level TEXT NOT NULL,
+                        reason TEXT NOT NULL,
+                        result_id INTEGER
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_transitions_monitor_time
+                        ON transitions (monitor, time);",
+                )
+                .map_err(|e| e.into())
+            })
+            .await?;
+        }
+
+        // create incidents and incident_events tables if they don't exist
+        debug!("attempting to create incident tables...");
+        if let Some(db) = self.db {
+            db.call(|db| {
+                db.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS incidents (
+                        id INTEGER PRIMARY KEY,
+                        monitor TEXT NOT NULL,
+                        opened_at INTEGER NOT NULL,
+                        peak_level TEXT NOT NULL,
+                        peak_level_index INTEGER NOT NULL,
+                        cleared_at INTEGER,
+                        resolution TEXT,
+                        ack_time INTEGER,
+                        ack_expires_at INTEGER,
+                        ack_by TEXT,
+                        ack_comment TEXT
+                    );
+                    CREATE TABLE IF NOT EXISTS incident_events (
+                        id INTEGER PRIMARY KEY,
+                        incident_id INTEGER NOT NULL,
+                        time INTEGER NOT NULL,
+                        kind TEXT NOT NULL,
+                        level TEXT NOT NULL,
+                        detail TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_incident_events_incident_id
+                        ON incident_events (incident_id);",
+                )
+                .map_err(|e| e.into())
+            })
+            .await?;
+        }
+
+        // columns added to incidents after the initial release
+        self.add_column("incidents", "ack_time", "INTEGER").await?;
+        self.add_column("incidents", "ack_expires_at", "INTEGER")
+            .await?;
+        self.add_column("incidents", "ack_by", "TEXT").await?;
+        self.add_column("incidents", "ack_comment", "TEXT").await?;
+        Ok(())
+    }
+
+    /// add a column to an existing table if it isn't already present,
+    /// used to migrate databases created by earlier versions
+    #[instrument]
+    async fn add_column(
+        &self,
+        table: &'static str,
+        column: &'static str,
+        decl: &'static str,
+    ) -> Result<(), tokio_rusqlite::Error> {
+        if let Some(db) = self.db {
+            db.call(move |db| {
+                let mut stmt = db.prepare(&format!("PRAGMA table_info({})", table))?;
+                let columns = stmt
+                    .query_map([], |row| row.get::<_, String>(1))?
+                    .collect::<std::result::Result<Vec<String>, rusqlite::Error>>()?;
+                if !columns.iter().any(|c| c == column) {
+                    debug!("adding column {} to table {}", column, table);
+                    db.execute(
+                        &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl),
+                        [],
+                    )?;
+                }
+                Ok(())
+            })
+            .await?;
+        }
         Ok(())
     }
 
     #[instrument]
-    pub async fn save_result(&self, res: MonitorResult) -> Result<(), tokio_rusqlite::Error> {
+    pub async fn save_result(&self, res: MonitorResult) -> Result<i64, tokio_rusqlite::Error> {
         let MonitorResult {
             name: monitor_name,
             level_name,
@@ -123,12 +362,22 @@ impl SynthDb {
             stdout,
             stderr,
             duration,
+            queue_time,
             status,
+            skipped,
+            silenced,
+            flapping: _,
+            acknowledged: _,
+            replica,
+            votes: _,
+            incident_id,
         } = res;
+        let mut id = 0;
         if let Some(db) = self.db {
-            db.call(move |db| {
-                db.execute(
-                    "INSERT INTO
+            id = db
+                .call(move |db| {
+                    db.execute(
+                        "INSERT INTO
                         results (
                             monitor_name, 
                             level_name,
@@ -138,7 +387,12 @@ impl SynthDb {
                             stdout,
                             stderr,
                             duration,
-                            status
+                            queue_time,
+                            status,
+                            skipped,
+                            silenced,
+                            incident_id,
+                            replica
                         )
                     VALUES (
                             ?1,
@@ -149,26 +403,43 @@ impl SynthDb {
                             ?6,
                             ?7,
                             ?8,
-                            ?9
+                            ?9,
+                            ?10,
+                            ?11,
+                            ?12,
+                            ?13,
+                            ?14
                         )
                     ",
-                    params![
-                        monitor_name,
-                        level_name,
-                        start_time,
-                        target_name,
-                        args,
-                        stdout,
-                        stderr,
-                        duration,
-                        status
-                    ],
-                )
-                .map_err(|e| e.into())
-            })
-            .await?;
+                        params![
+                            monitor_name,
+                            level_name,
+                            start_time,
+                            target_name,
+                            args,
+                            stdout,
+                            stderr,
+                            duration,
+                            queue_time,
+                            status,
+                            skipped,
+                            silenced,
+                            incident_id,
+                            replica
+                        ],
+                    )?;
+                    Ok(db.last_insert_rowid())
+                })
+                .await?;
         }
-        Ok(())
+        if self.results_since_prune.fetch_add(1, Ordering::Relaxed) + 1 >= PRUNE_EVERY {
+            self.results_since_prune.store(0, Ordering::Relaxed);
+            // the result was saved, so a failed prune is only logged
+            if let Err(e) = self.prune_results(start_time).await {
+                error!("failed to prune results ({})", e);
+            }
+        }
+        Ok(id)
     }
 
     #[instrument]
@@ -240,20 +511,14 @@ impl SynthDb {
         Ok(())
     }
 
-    /// save the current state of the monitor to db, including:
-    ///   - level_index
-    ///   - failure_tally
-    ///   - success_tally
-    ///
-    /// updates any previous entry
+    /// save the current state of the monitor to db, updates any previous entry
     #[instrument]
     pub async fn save_monitor_state(
         &self,
         mon: String,
-        level_index: usize,
-        failure_tally: u64,
-        success_tally: u64,
+        state: MonitorState,
     ) -> Result<(), tokio_rusqlite::Error> {
+        let notified = serde_json::to_string(&state.notified).unwrap_or(String::from("[]"));
         if let Some(db) = self.db {
             db.call(move |db| {
                 db.execute(
@@ -262,22 +527,51 @@ impl SynthDb {
                             name,
                             level_index,
                             failure_tally,
-                            success_tally
+                            success_tally,
+                            streak_start,
+                            flapping,
+                            notified,
+                            incident_id,
+                            acknowledged,
+                            clear_pending
                         )
                         VALUES (
                             ?1,
                             ?2,
                             ?3,
-                            ?4
+                            ?4,
+                            ?5,
+                            ?6,
+                            ?7,
+                            ?8,
+                            ?9,
+                            ?10
                         )
                     ON CONFLICT (name) DO
                     UPDATE
                     SET
                         level_index = EXCLUDED.level_index,
                         failure_tally = EXCLUDED.failure_tally,
-                        success_tally = EXCLUDED.success_tally
+                        success_tally = EXCLUDED.success_tally,
+                        streak_start = EXCLUDED.streak_start,
+                        flapping = EXCLUDED.flapping,
+                        notified = EXCLUDED.notified,
+                        incident_id = EXCLUDED.incident_id,
+                        acknowledged = EXCLUDED.acknowledged,
+                        clear_pending = EXCLUDED.clear_pending
                     ",
-                    params![mon, level_index, failure_tally, success_tally],
+                    params![
+                        mon,
+                        state.level_index,
+                        state.failure_tally,
+                        state.success_tally,
+                        state.streak_start,
+                        state.flapping,
+                        notified,
+                        state.incident_id,
+                        state.acknowledged,
+                        state.clear_pending
+                    ],
                 )
                 .map_err(|e| e.into())
             })
@@ -290,49 +584,326 @@ impl SynthDb {
     pub async fn get_monitor_state(
         &self,
         mon: String,
-    ) -> Result<Option<(usize, u64, u64)>, tokio_rusqlite::Error> {
-        type MonitorState = (String, usize, u64, u64);
+    ) -> Result<Option<MonitorState>, tokio_rusqlite::Error> {
         let mut state = Vec::new();
         if let Some(db) = self.db {
             state = db
                 .call(move |db| {
                     let mut stmt = db.prepare(
                         "SELECT
-                        name,
                         level_index,
                         failure_tally,
-                        success_tally
+                        success_tally,
+                        streak_start,
+                        flapping,
+                        notified,
+                        incident_id,
+                        acknowledged,
+                        clear_pending
                     FROM monitor_state WHERE name == ?1",
                     )?;
                     let state = stmt
                         .query_map([mon], |row| {
-                            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                            Ok(MonitorState {
+                                level_index: row.get(0)?,
+                                failure_tally: row.get(1)?,
+                                success_tally: row.get(2)?,
+                                streak_start: row.get(3)?,
+                                flapping: row.get(4)?,
+                                notified: serde_json::from_str(&row.get::<_, String>(5)?)
+                                    .unwrap_or_default(),
+                                incident_id: row.get(6)?,
+                                acknowledged: row.get(7)?,
+                                clear_pending: row.get(8)?,
+                            })
                         })?
                         .collect::<std::result::Result<Vec<MonitorState>, rusqlite::Error>>()?;
                     Ok(state)
                 })
                 .await?;
         }
-        if let Some(s) = state.last() {
-            return Ok(Some((s.1, s.2, s.3)));
+        Ok(state.pop())
+    }
+
+    /// get the start time and status of the most recent non-skipped results
+    /// for a monitor, at least the last `count` and all since `since` (ms),
+    /// oldest first. Individual outcomes of replicated runs are excluded
+    #[instrument]
+    pub async fn get_recent_results(
+        &self,
+        mon: String,
+        count: usize,
+        since: u64,
+    ) -> Result<Vec<(u64, i32)>, tokio_rusqlite::Error> {
+        let mut results = Vec::new();
+        if let Some(db) = self.db {
+            results = db
+                .call(move |db| {
+                    let mut stmt = db.prepare(
+                        "SELECT start_time, status FROM (
+                            SELECT id, start_time, status FROM results
+                            WHERE monitor_name = ?1 AND skipped = 0 AND replica IS NULL
+                                AND start_time >= ?3
+                            UNION
+                            SELECT * FROM (
+                                SELECT id, start_time, status FROM results
+                                WHERE monitor_name = ?1 AND skipped = 0 AND replica IS NULL
+                                ORDER BY id DESC LIMIT ?2
+                            )
+                        ) ORDER BY id",
+                    )?;
+                    let results = stmt
+                        .query_map(params![mon, count, since], |row| {
+                            Ok((row.get(0)?, row.get(1)?))
+                        })?
+                        .collect::<std::result::Result<Vec<(u64, i32)>, rusqlite::Error>>()?;
+                    Ok(results)
+                })
+                .await?;
         }
-        Ok(None)
+        Ok(results)
+    }
+
+    /// prune each monitor's results down to the most recent 500 runs, or
+    /// the history its windowed rules need if longer, called periodically
+    /// by save_result. Skipped runs and replica outcomes don't count as
+    /// runs and are pruned along with the runs around them
+    #[instrument]
+    pub async fn prune_results(&self, now: u64) -> Result<(), tokio_rusqlite::Error> {
+        if let Some(db) = self.db {
+            let retention = self
+                .retention
+                .lock()
+                .expect("retention lock poisoned")
+                .clone();
+            db.call(move |db| {
+                let monitors = db
+                    .prepare("SELECT DISTINCT monitor_name FROM results")?
+                    .query_map([], |row| row.get(0))?
+                    .collect::<std::result::Result<Vec<String>, rusqlite::Error>>()?;
+                for m in monitors {
+                    let (runs, age) = retention.get(&m).copied().unwrap_or_default();
+                    // oldest run kept by count, anything older that's
+                    // also outside the age is pruned
+                    let oldest: Option<i64> = db.query_row(
+                        "SELECT MIN(id) FROM (
+                            SELECT id FROM results
+                            WHERE monitor_name = ?1 AND skipped = 0 AND replica IS NULL
+                            ORDER BY id DESC LIMIT ?2
+                        )",
+                        params![m, runs.max(RESULTS_RETAINED)],
+                        |row| row.get(0),
+                    )?;
+                    let Some(oldest) = oldest else {
+                        continue;
+                    };
+                    db.execute(
+                        "DELETE FROM results
+                        WHERE monitor_name = ?1 AND id < ?2 AND start_time < ?3",
+                        params![m, oldest, now.saturating_sub(age)],
+                    )?;
+                }
+                Ok(())
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// add a new silence, returning its id
+    #[instrument]
+    pub async fn add_silence(&self, silence: Silence) -> Result<i64, tokio_rusqlite::Error> {
+        let mut id = 0;
+        if let Some(db) = self.db {
+            id = db
+                .call(move |db| {
+                    db.execute(
+                        "INSERT INTO
+                            silences (
+                                monitor,
+                                tag,
+                                start_time,
+                                end_time,
+                                creator,
+                                comment
+                            )
+                            VALUES (
+                                ?1,
+                                ?2,
+                                ?3,
+                                ?4,
+                                ?5,
+                                ?6
+                            )",
+                        params![
+                            silence.matcher.monitor,
+                            silence.matcher.tag,
+                            silence.start_time,
+                            silence.end_time,
+                            silence.creator,
+                            silence.comment
+                        ],
+                    )?;
+                    Ok(db.last_insert_rowid())
+                })
+                .await?;
+        }
+        Ok(id)
+    }
+
+    /// get silences that haven't yet ended as of the given time (ms), or
+    /// all silences if no time is given
+    #[instrument]
+    pub async fn get_silences(
+        &self,
+        now: Option<u64>,
+    ) -> Result<Vec<Silence>, tokio_rusqlite::Error> {
+        let mut silences = Vec::new();
+        if let Some(db) = self.db {
+            silences = db
+                .call(move |db| {
+                    let mut stmt = db.prepare(
+                        "SELECT
+                            id,
+                            monitor,
+                            tag,
+                            start_time,
+                            end_time,
+                            creator,
+                            comment
+                        FROM silences WHERE end_time > ?1
+                        ORDER BY start_time",
+                    )?;
+                    let silences = stmt
+                        .query_map([now.unwrap_or(0)], |row| {
+                            Ok(Silence {
+                                id: row.get(0)?,
+                                matcher: MonitorMatcher {
+                                    monitor: row.get(1)?,
+                                    tag: row.get(2)?,
+                                },
+                                start_time: row.get(3)?,
+                                end_time: row.get(4)?,
+                                creator: row.get(5)?,
+                                comment: row.get(6)?,
+                            })
+                        })?
+                        .collect::<std::result::Result<Vec<Silence>, rusqlite::Error>>()?;
+                    Ok(silences)
+                })
+                .await?;
+        }
+        Ok(silences)
     }
 
-    /// prune results table down to the most recent 500
+    /// remove a silence by id, returning false if it didn't exist
     #[instrument]
-    pub async fn prune_results(&self) -> Result<(), tokio_rusqlite::Error> {
+    pub async fn remove_silence(&self, id: i64) -> Result<bool, tokio_rusqlite::Error> {
+        let mut removed = 0;
+        if let Some(db) = self.db {
+            removed = db
+                .call(move |db| {
+                    db.execute("DELETE FROM silences WHERE id = ?1", [id])
+                        .map_err(|e| e.into())
+                })
+                .await?;
+        }
+        Ok(removed > 0)
+    }
+
+    /// open a new incident for a monitor, returning its id
+    #[instrument]
+    pub async fn open_incident(
+        &self,
+        mon: String,
+        opened_at: u64,
+        level: String,
+        level_index: usize,
+    ) -> Result<i64, tokio_rusqlite::Error> {
+        let mut id = 0;
+        if let Some(db) = self.db {
+            id = db
+                .call(move |db| {
+                    db.execute(
+                        "INSERT INTO
+                            incidents (
+                                monitor,
+                                opened_at,
+                                peak_level,
+                                peak_level_index
+                            )
+                            VALUES (
+                                ?1,
+                                ?2,
+                                ?3,
+                                ?4
+                            )",
+                        params![mon, opened_at, level, level_index],
+                    )?;
+                    Ok(db.last_insert_rowid())
+                })
+                .await?;
+        }
+        Ok(id)
+    }
+
+    /// record a step in the history of an incident, raising the peak
+    /// level of the incident if the level is higher
+    #[instrument]
+    pub async fn add_incident_event(
+        &self,
+        id: i64,
+        event: IncidentEvent,
+        level_index: usize,
+    ) -> Result<(), tokio_rusqlite::Error> {
         if let Some(db) = self.db {
             db.call(move |db| {
                 db.execute(
-                    "DELETE FROM results 
-                    WHERE id NOT IN 
-                    (
-                        SELECT id FROM results 
-                        ORDER BY (id) 
-                        DESC LIMIT 500
-                    )",
-                    (),
+                    "INSERT INTO
+                        incident_events (
+                            incident_id,
+                            time,
+                            kind,
+                            level,
+                            detail
+                        )
+                        VALUES (
+                            ?1,
+                            ?2,
+                            ?3,
+                            ?4,
+                            ?5
+                        )",
+                    params![id, event.time, event.kind, event.level, event.detail],
+                )?;
+                db.execute(
+                    "UPDATE incidents
+                    SET
+                        peak_level = ?2,
+                        peak_level_index = ?3
+                    WHERE id = ?1 AND peak_level_index < ?3",
+                    params![id, event.level, level_index],
+                )?;
+                Ok(())
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// mark an incident as cleared
+    #[instrument]
+    pub async fn close_incident(
+        &self,
+        id: i64,
+        cleared_at: u64,
+        resolution: String,
+    ) -> Result<(), tokio_rusqlite::Error> {
+        if let Some(db) = self.db {
+            db.call(move |db| {
+                db.execute(
+                    "UPDATE incidents SET cleared_at = ?2, resolution = ?3 WHERE id = ?1",
+                    params![id, cleared_at, resolution],
                 )
                 .map_err(|e| e.into())
             })
@@ -340,4 +911,281 @@ impl SynthDb {
         }
         Ok(())
     }
+
+    /// get the most recent incidents, newest first, optionally only
+    /// those that are still open
+    #[instrument]
+    pub async fn get_incidents(
+        &self,
+        open_only: bool,
+        limit: u64,
+    ) -> Result<Vec<Incident>, tokio_rusqlite::Error> {
+        let mut incidents = Vec::new();
+        if let Some(db) = self.db {
+            incidents = db
+                .call(move |db| {
+                    let mut stmt = db.prepare(
+                        "SELECT
+                            id,
+                            monitor,
+                            opened_at,
+                            peak_level,
+                            cleared_at,
+                            resolution,
+                            ack_time,
+                            ack_expires_at,
+                            ack_by,
+                            ack_comment
+                        FROM incidents WHERE NOT ?1 OR cleared_at IS NULL
+                        ORDER BY id DESC
+                        LIMIT ?2",
+                    )?;
+                    let incidents = stmt
+                        .query_map(params![open_only, limit], |row| {
+                            Ok(Incident {
+                                id: row.get(0)?,
+                                monitor: row.get(1)?,
+                                opened_at: row.get(2)?,
+                                peak_level: row.get(3)?,
+                                cleared_at: row.get(4)?,
+                                resolution: row.get(5)?,
+                                ack: ack_from_row(row, 6)?,
+                            })
+                        })?
+                        .collect::<std::result::Result<Vec<Incident>, rusqlite::Error>>()?;
+                    Ok(incidents)
+                })
+                .await?;
+        }
+        Ok(incidents)
+    }
+
+    /// get the history of an incident, oldest first
+    #[instrument]
+    pub async fn get_incident_events(
+        &self,
+        id: i64,
+    ) -> Result<Vec<IncidentEvent>, tokio_rusqlite::Error> {
+        let mut events = Vec::new();
+        if let Some(db) = self.db {
+            events = db
+                .call(move |db| {
+                    let mut stmt = db.prepare(
+                        "SELECT
+                            time,
+                            kind,
+                            level,
+                            detail
+                        FROM incident_events WHERE incident_id = ?1
+                        ORDER BY id",
+                    )?;
+                    let events = stmt
+                        .query_map([id], |row| {
+                            Ok(IncidentEvent {
+                                time: row.get(0)?,
+                                kind: row.get(1)?,
+                                level: row.get(2)?,
+                                detail: row.get(3)?,
+                            })
+                        })?
+                        .collect::<std::result::Result<Vec<IncidentEvent>, rusqlite::Error>>()?;
+                    Ok(events)
+                })
+                .await?;
+        }
+        Ok(events)
+    }
+
+    /// record a level transition of a monitor
+    #[instrument]
+    pub async fn save_transition(&self, t: Transition) -> Result<(), tokio_rusqlite::Error> {
+        if let Some(db) = self.db {
+            db.call(move |db| {
+                db.execute(
+                    "INSERT INTO
+                        transitions (
+                            monitor,
+                            time,
+                            from_level,
+                            to_level,
+                            reason,
+                            result_id
+                        )
+                        VALUES (
+                            ?1,
+                            ?2,
+                            ?3,
+                            ?4,
+                            ?5,
+                            ?6
+                        )",
+                    params![
+                        t.monitor,
+                        t.time,
+                        t.from_level,
+                        t.to_level,
+                        t.reason,
+                        t.result_id
+                    ],
+                )?;
+                // transitions are rare, so the monitor's old ones are
+                // pruned as each new one is recorded
+                if let Err(e) = db.execute(
+                    "DELETE FROM transitions WHERE monitor = ?1 AND time < ?2",
+                    params![t.monitor, t.time.saturating_sub(TRANSITIONS_RETAINED_MS)],
+                ) {
+                    error!("failed to prune transitions ({})", e);
+                }
+                Ok(())
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// get the most recent transitions since the given time (ms), newest
+    /// first, for monitors matching an optional name pattern
+    #[instrument]
+    pub async fn get_transitions(
+        &self,
+        monitor: Option<String>,
+        since: u64,
+        limit: u64,
+    ) -> Result<Vec<Transition>, tokio_rusqlite::Error> {
+        let mut transitions = Vec::new();
+        if let Some(db) = self.db {
+            transitions = db
+                .call(move |db| {
+                    let mut stmt = db.prepare(
+                        "SELECT
+                            monitor,
+                            time,
+                            from_level,
+                            to_level,
+                            reason,
+                            result_id
+                        FROM transitions WHERE time >= ?1
+                        ORDER BY id DESC",
+                    )?;
+                    let transitions = stmt
+                        .query_map([since], |row| {
+                            Ok(Transition {
+                                monitor: row.get(0)?,
+                                time: row.get(1)?,
+                                from_level: row.get(2)?,
+                                to_level: row.get(3)?,
+                                reason: row.get(4)?,
+                                result_id: row.get(5)?,
+                            })
+                        })?
+                        .filter(|t| match (t, &monitor) {
+                            (Ok(t), Some(m)) => glob_match(m, &t.monitor),
+                            _ => true,
+                        })
+                        .take(limit as usize)
+                        .collect::<std::result::Result<Vec<Transition>, rusqlite::Error>>()?;
+                    Ok(transitions)
+                })
+                .await?;
+        }
+        Ok(transitions)
+    }
+
+    /// acknowledge an open incident, replacing any earlier
+    /// acknowledgement, returning false if there's no such open incident
+    #[instrument]
+    pub async fn acknowledge_incident(
+        &self,
+        id: i64,
+        ack: Acknowledgement,
+    ) -> Result<bool, tokio_rusqlite::Error> {
+        let mut updated = 0;
+        if let Some(db) = self.db {
+            updated = db
+                .call(move |db| {
+                    db.execute(
+                        "UPDATE incidents
+                        SET
+                            ack_time = ?2,
+                            ack_expires_at = ?3,
+                            ack_by = ?4,
+                            ack_comment = ?5
+                        WHERE id = ?1 AND cleared_at IS NULL",
+                        params![id, ack.time, ack.expires_at, ack.by, ack.comment],
+                    )
+                    .map_err(|e| e.into())
+                })
+                .await?;
+        }
+        Ok(updated > 0)
+    }
+
+    /// remove the acknowledgement of an incident, returning false if
+    /// it wasn't acknowledged
+    #[instrument]
+    pub async fn unacknowledge_incident(&self, id: i64) -> Result<bool, tokio_rusqlite::Error> {
+        let mut updated = 0;
+        if let Some(db) = self.db {
+            updated = db
+                .call(move |db| {
+                    db.execute(
+                        "UPDATE incidents
+                        SET
+                            ack_time = NULL,
+                            ack_expires_at = NULL,
+                            ack_by = NULL,
+                            ack_comment = NULL
+                        WHERE id = ?1 AND ack_time IS NOT NULL",
+                        [id],
+                    )
+                    .map_err(|e| e.into())
+                })
+                .await?;
+        }
+        Ok(updated > 0)
+    }
+
+    /// get the acknowledgement of an incident, if any
+    #[instrument]
+    pub async fn get_incident_ack(
+        &self,
+        id: i64,
+    ) -> Result<Option<Acknowledgement>, tokio_rusqlite::Error> {
+        let mut ack = None;
+        if let Some(db) = self.db {
+            ack = db
+                .call(move |db| {
+                    let mut stmt = db.prepare(
+                        "SELECT
+                            ack_time,
+                            ack_expires_at,
+                            ack_by,
+                            ack_comment
+                        FROM incidents WHERE id = ?1",
+                    )?;
+                    let ack = stmt
+                        .query_map([id], |row| ack_from_row(row, 0))?
+                        .collect::<std::result::Result<Vec<Option<Acknowledgement>>, rusqlite::Error>>()?;
+                    Ok(ack.into_iter().flatten().next())
+                })
+                .await?;
+        }
+        Ok(ack)
+    }
+}
+
+/// read the acknowledgement columns of an incident starting at `idx`
+fn ack_from_row(
+    row: &rusqlite::Row,
+    idx: usize,
+) -> Result<Option<Acknowledgement>, rusqlite::Error> {
+    let Some(time) = row.get(idx)? else {
+        return Ok(None);
+    };
+    Ok(Some(Acknowledgement {
+        time,
+        expires_at: row.get(idx + 1)?,
+        by: row.get::<_, Option<String>>(idx + 2)?.unwrap_or_default(),
+        comment: row.get::<_, Option<String>>(idx + 3)?.unwrap_or_default(),
+    }))
 }
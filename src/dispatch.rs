@@ -0,0 +1,235 @@
+//! Shared dispatch of notifications across monitors.
+//!
+//! Monitors ask the dispatcher before sending each level notification to
+//! a reporter. Within each digest window the first notifications to a
+//! reporter are sent as normal, beyond the threshold they are held and
+//! sent as a single digest at the end of the window, so an alert storm
+//! produces one message rather than one per monitor. An optional rate
+//! limit caps the notifications sent to a reporter each minute, anything
+//! over the cap is added to the digest or dropped if the reporter has no
+//! digest support. Only failures are counted, passing results, clears,
+//! flap and acknowledgement notifications are always sent, so a batched
+//! failure is still cleared by the monitor once it recovers.
+//!
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use crate::config;
+use crate::monitor::MonitorResult;
+use crate::reporters::{Reporter, ReporterArgs};
+
+const RATE_PERIOD: Duration = Duration::from_secs(60);
+
+pub struct Dispatcher {
+    digest_window: Option<Duration>,
+    digest_threshold: usize,
+    /// maximum notifications per minute, by reporter
+    rate_limits: HashMap<String, u32>,
+    state: Mutex<HashMap<String, ReporterWindow>>,
+    digest_names: HashSet<String>,
+    /// reporters digests are delivered through, by name
+    digest_reporters:
+        tokio::sync::Mutex<HashMap<String, Box<dyn Reporter + Send + Sync + 'static>>>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct DispatchArgs {
+    /// enables batching of notifications into digests
+    #[serde(default, deserialize_with = "config::deserialize_opt_duration")]
+    digest_window: Option<Duration>,
+    /// notifications sent to a reporter in a window before the
+    /// rest are batched, defaults to 5
+    digest_threshold: Option<usize>,
+    rate_limit: Option<HashMap<String, u32>>,
+}
+
+/// Whether a notification should be sent by the monitor
+#[derive(Debug, PartialEq)]
+pub enum Admission {
+    Send,
+    /// held for the next digest
+    Batched,
+    /// over the rate limit of a reporter without digest support
+    Dropped,
+}
+
+#[derive(Debug)]
+struct ReporterWindow {
+    sent: usize,
+    held: Vec<DigestEntry>,
+    rate_start: Instant,
+    rate_sent: u32,
+}
+
+/// Notifications held during a digest window
+#[derive(Serialize, Debug)]
+pub struct Digest {
+    pub reporter: String,
+    pub window: String,
+    /// number of notifications held
+    pub count: usize,
+    /// number of distinct monitors with held notifications
+    pub monitors: usize,
+    pub entries: Vec<DigestEntry>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DigestEntry {
+    pub monitor: String,
+    pub level_name: String,
+    pub start_time: u64,
+    pub status: i32,
+    pub incident_id: Option<i64>,
+}
+
+impl DispatchArgs {
+    /// build the dispatcher, rate limits must name a configured reporter
+    pub fn build(self, reporters: &[ReporterArgs]) -> Result<Dispatcher, String> {
+        if self.digest_window.is_some_and(|w| w.is_zero()) {
+            return Err(String::from("digest_window must be greater than zero"));
+        }
+        let rate_limits: HashMap<String, u32> = self
+            .rate_limit
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(k, v)| (k.to_lowercase(), v))
+            .collect();
+        if let Some((k, _)) = rate_limits.iter().find(|(_, v)| **v == 0) {
+            return Err(format!("rate_limit for {} must be greater than zero", k));
+        }
+        if let Some(k) = rate_limits
+            .keys()
+            .find(|k| !reporters.iter().any(|r| r.name.to_lowercase() == **k))
+        {
+            return Err(format!("rate_limit for unknown reporter {}", k));
+        }
+        Ok(Dispatcher {
+            digest_window: self.digest_window,
+            digest_threshold: self.digest_threshold.unwrap_or(5),
+            rate_limits,
+            state: Mutex::new(HashMap::new()),
+            digest_names: HashSet::new(),
+            digest_reporters: tokio::sync::Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+impl Dispatcher {
+    /// Registers the reporter digests for the named reporter are sent
+    /// through, notifications are only batched for reporters registered here
+    pub fn register_reporter(
+        &mut self,
+        name: &str,
+        rep: Box<dyn Reporter + Send + Sync + 'static>,
+    ) {
+        if self.digest_window.is_none() {
+            return;
+        }
+        self.digest_names.insert(name.to_lowercase());
+        self.digest_reporters
+            .get_mut()
+            .insert(name.to_lowercase(), rep);
+        info!("registered digest reporter: {}", name);
+    }
+
+    /// Decide whether a monitor should send a notification to the
+    /// reporter now, counting it against the window and rate limit
+    pub fn admit(&self, reporter: &str, res: &MonitorResult, batch: bool) -> Admission {
+        let batching = batch && self.digest_names.contains(reporter);
+        let now = Instant::now();
+        let mut state = self.state.lock().expect("dispatch state lock poisoned");
+        let w = state
+            .entry(reporter.to_string())
+            .or_insert_with(|| ReporterWindow {
+                sent: 0,
+                held: Vec::new(),
+                rate_start: now,
+                rate_sent: 0,
+            });
+        if now - w.rate_start >= RATE_PERIOD {
+            w.rate_start = now;
+            w.rate_sent = 0;
+        }
+        let limited = self
+            .rate_limits
+            .get(reporter)
+            .is_some_and(|l| w.rate_sent >= *l);
+        if batching && (limited || w.sent >= self.digest_threshold) {
+            w.held.push(DigestEntry {
+                monitor: res.name.clone(),
+                level_name: res.level_name.clone(),
+                start_time: res.start_time,
+                status: res.status,
+                incident_id: res.incident_id,
+            });
+            return Admission::Batched;
+        }
+        if limited {
+            return Admission::Dropped;
+        }
+        w.sent += 1;
+        w.rate_sent += 1;
+        Admission::Send
+    }
+
+    /// Send a digest of the held notifications at the end of each
+    /// window, and once more on shutdown
+    pub async fn run(&self, cancel: CancellationToken) {
+        let Some(window) = self.digest_window else {
+            return;
+        };
+        let mut ticker = tokio::time::interval_at(Instant::now() + window, window);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => self.flush(window).await,
+                _ = cancel.cancelled() => {
+                    self.flush(window).await;
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn flush(&self, window: Duration) {
+        let digests: Vec<Digest> = {
+            let mut state = self.state.lock().expect("dispatch state lock poisoned");
+            state
+                .iter_mut()
+                .filter_map(|(reporter, w)| {
+                    w.sent = 0;
+                    if w.held.is_empty() {
+                        return None;
+                    }
+                    let entries = std::mem::take(&mut w.held);
+                    let monitors = entries
+                        .iter()
+                        .map(|e| &e.monitor)
+                        .collect::<HashSet<_>>()
+                        .len();
+                    Some(Digest {
+                        reporter: reporter.clone(),
+                        window: humantime::format_duration(window).to_string(),
+                        count: entries.len(),
+                        monitors,
+                        entries,
+                    })
+                })
+                .collect()
+        };
+        let mut reporters = self.digest_reporters.lock().await;
+        for d in digests {
+            if let Some(r) = reporters.get_mut(&d.reporter) {
+                info!(
+                    "sending {} digest of {} held notifications",
+                    d.reporter, d.count
+                );
+                r.digest(&d).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,439 @@
+//! Boolean expressions over the status of other monitors, evaluated
+//! against the shared registry by composite monitors.
+//!
+//! A bare monitor name is true while that monitor's last run failed,
+//! and the following functions are available, where only the patterns
+//! of the count functions may include '*' and '?' wildcards:
+//!
+//! - `failing(name)` last run of the monitor failed
+//! - `escalated(name)` monitor is above its base level
+//! - `level(name)` level index of the monitor (0 is the base level)
+//! - `count_failing(pattern, ...)` number of matching monitors failing
+//! - `count_escalated(pattern, ...)` number of matching monitors escalated
+//!
+//! Expressions combine these with `&&`, `||`, `!`, parentheses,
+//! integers and the comparisons `==`, `!=`, `<`, `<=`, `>` and `>=`.
+//! Names containing spaces or operators can be quoted, e.g. `"api eu"`.
+//!
+use std::collections::HashMap;
+
+use crate::registry::{MonitorStatus, Registry};
+
+#[derive(Clone, Debug)]
+pub enum Expr {
+    Bool(bool),
+    Num(i64),
+    Call(Func, Vec<String>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Cmp(Box<Expr>, CmpOp, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Func {
+    Failing,
+    Escalated,
+    Level,
+    CountFailing,
+    CountEscalated,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Name(String),
+    Num(i64),
+    LParen,
+    RParen,
+    Not,
+    And,
+    Or,
+    Cmp(CmpOp),
+    Comma,
+}
+
+impl Expr {
+    pub fn parse(src: &str) -> Result<Expr, String> {
+        let tokens = tokenize(src)?;
+        let mut p = Parser { tokens, pos: 0 };
+        let expr = p.or()?;
+        if p.pos < p.tokens.len() {
+            return Err(format!("unexpected {:?}", p.tokens[p.pos]));
+        }
+        Ok(expr)
+    }
+
+    /// evaluate as a boolean, numbers are true when non-zero
+    pub fn is_true(&self, registry: &Registry) -> bool {
+        match self {
+            Expr::Bool(b) => *b,
+            Expr::Not(e) => !e.is_true(registry),
+            Expr::And(a, b) => a.is_true(registry) && b.is_true(registry),
+            Expr::Or(a, b) => a.is_true(registry) || b.is_true(registry),
+            Expr::Cmp(a, op, b) => {
+                let (a, b) = (a.value(registry), b.value(registry));
+                match op {
+                    CmpOp::Eq => a == b,
+                    CmpOp::Ne => a != b,
+                    CmpOp::Lt => a < b,
+                    CmpOp::Le => a <= b,
+                    CmpOp::Gt => a > b,
+                    CmpOp::Ge => a >= b,
+                }
+            }
+            _ => self.value(registry) != 0,
+        }
+    }
+
+    /// evaluate as a number, booleans are 1 when true
+    fn value(&self, registry: &Registry) -> i64 {
+        match self {
+            Expr::Num(n) => *n,
+            Expr::Call(f, names) => {
+                let name = &names[0];
+                match f {
+                    Func::Failing => registry.get(name).is_some_and(|s| s.failing) as i64,
+                    Func::Escalated => registry.get(name).is_some_and(|s| s.escalated()) as i64,
+                    Func::Level => registry.get(name).map_or(0, |s| s.level_index as i64),
+                    Func::CountFailing => matching(registry, names)
+                        .values()
+                        .filter(|s| s.failing)
+                        .count() as i64,
+                    Func::CountEscalated => matching(registry, names)
+                        .values()
+                        .filter(|s| s.escalated())
+                        .count() as i64,
+                }
+            }
+            _ => self.is_true(registry) as i64,
+        }
+    }
+
+    /// names and patterns of the monitors referenced by the expression
+    pub fn references(&self) -> Vec<String> {
+        match self {
+            Expr::Call(_, names) => names.clone(),
+            Expr::Not(e) => e.references(),
+            Expr::And(a, b) | Expr::Or(a, b) | Expr::Cmp(a, _, b) => {
+                let mut r = a.references();
+                r.extend(b.references());
+                r
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// the referenced names that aren't wildcard patterns, each of which
+    /// must be a configured monitor
+    pub fn names(&self) -> Vec<String> {
+        self.references()
+            .into_iter()
+            .filter(|r| !is_pattern(r))
+            .collect()
+    }
+}
+
+fn is_pattern(name: &str) -> bool {
+    name.contains(['*', '?'])
+}
+
+/// monitors matching any of the patterns, each counted once
+fn matching(registry: &Registry, patterns: &[String]) -> HashMap<String, MonitorStatus> {
+    patterns.iter().flat_map(|p| registry.matching(p)).collect()
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut next_is = |n: char| chars.next_if_eq(&n).is_some();
+        let t = match c {
+            ' ' | '\t' | '\n' => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            ',' => Token::Comma,
+            '&' if next_is('&') => Token::And,
+            '|' if next_is('|') => Token::Or,
+            '=' if next_is('=') => Token::Cmp(CmpOp::Eq),
+            '!' if next_is('=') => Token::Cmp(CmpOp::Ne),
+            '!' => Token::Not,
+            '<' if next_is('=') => Token::Cmp(CmpOp::Le),
+            '<' => Token::Cmp(CmpOp::Lt),
+            '>' if next_is('=') => Token::Cmp(CmpOp::Ge),
+            '>' => Token::Cmp(CmpOp::Gt),
+            '"' | '\'' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some(n) => name.push(n),
+                        None => return Err(String::from("unterminated quoted name")),
+                    }
+                }
+                Token::Name(name)
+            }
+            c if is_name_char(c) => {
+                let mut name = String::from(c);
+                while let Some(n) = chars.next_if(|n| is_name_char(*n)) {
+                    name.push(n);
+                }
+                match name.parse::<i64>() {
+                    Ok(n) => Token::Num(n),
+                    Err(_) => Token::Name(name),
+                }
+            }
+            c => return Err(format!("unexpected character '{}'", c)),
+        };
+        tokens.push(t);
+    }
+    Ok(tokens)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '*' | '?')
+}
+
+/// recursive descent parser, from lowest to highest precedence:
+/// `||`, `&&`, comparisons, `!`
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn next_if(&mut self, t: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(t) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut e = self.and()?;
+        while self.next_if(&Token::Or) {
+            e = Expr::Or(Box::new(e), Box::new(self.and()?));
+        }
+        Ok(e)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut e = self.cmp()?;
+        while self.next_if(&Token::And) {
+            e = Expr::And(Box::new(e), Box::new(self.cmp()?));
+        }
+        Ok(e)
+    }
+
+    fn cmp(&mut self) -> Result<Expr, String> {
+        let e = self.unary()?;
+        if let Some(Token::Cmp(op)) = self.tokens.get(self.pos).cloned() {
+            self.pos += 1;
+            return Ok(Expr::Cmp(Box::new(e), op, Box::new(self.unary()?)));
+        }
+        Ok(e)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.next_if(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Expr::Num(n)),
+            Some(Token::LParen) => {
+                let e = self.or()?;
+                if !self.next_if(&Token::RParen) {
+                    return Err(String::from("missing closing parenthesis"));
+                }
+                Ok(e)
+            }
+            Some(Token::Name(name)) => {
+                if !self.next_if(&Token::LParen) {
+                    return Ok(match name.as_str() {
+                        "true" => Expr::Bool(true),
+                        "false" => Expr::Bool(false),
+                        _ if is_pattern(&name) => return Err(wildcard(&name)),
+                        _ => Expr::Call(Func::Failing, vec![name]),
+                    });
+                }
+                let func = match name.as_str() {
+                    "failing" => Func::Failing,
+                    "escalated" => Func::Escalated,
+                    "level" => Func::Level,
+                    "count_failing" => Func::CountFailing,
+                    "count_escalated" => Func::CountEscalated,
+                    _ => return Err(format!("unknown function {}", name)),
+                };
+                let mut args = Vec::new();
+                loop {
+                    let Some(Token::Name(arg)) = self.next() else {
+                        return Err(format!("{} expects a monitor name", name));
+                    };
+                    args.push(arg);
+                    if !self.next_if(&Token::Comma) {
+                        break;
+                    }
+                }
+                if !self.next_if(&Token::RParen) {
+                    return Err(String::from("missing closing parenthesis"));
+                }
+                let counts = matches!(func, Func::CountFailing | Func::CountEscalated);
+                if args.len() > 1 && !counts {
+                    return Err(format!("{} expects a single monitor name", name));
+                }
+                if let Some(p) = args.iter().find(|a| is_pattern(a) && !counts) {
+                    return Err(wildcard(p));
+                }
+                Ok(Expr::Call(func, args))
+            }
+            Some(t) => Err(format!("unexpected {:?}", t)),
+            None => Err(String::from("unexpected end of expression")),
+        }
+    }
+}
+
+fn wildcard(pattern: &str) -> String {
+    format!(
+        "wildcard {} is only supported by count_failing and count_escalated",
+        pattern
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(monitors: &[(&str, usize, bool)]) -> Registry {
+        let r = Registry::default();
+        for (name, level_index, failing) in monitors {
+            r.update(
+                name,
+                MonitorStatus {
+                    level_index: *level_index,
+                    held: false,
+                    failing: *failing,
+                    last_run: 0,
+                },
+            );
+        }
+        r
+    }
+
+    fn eval(src: &str, r: &Registry) -> bool {
+        Expr::parse(src).unwrap().is_true(r)
+    }
+
+    #[test]
+    fn precedence() {
+        let r = Registry::default();
+        // && binds tighter than ||
+        assert!(eval("true || true && false", &r));
+        assert!(!eval("(true || true) && false", &r));
+        // ! binds tighter than &&
+        assert!(!eval("!true && false", &r));
+        assert!(eval("!(true && false)", &r));
+        // comparisons bind tighter than && and ||
+        assert!(eval("1 < 2 && 3 >= 3", &r));
+        assert!(eval("1 == 2 || 2 != 3", &r));
+        assert!(eval("!false == 1", &r));
+    }
+
+    #[test]
+    fn monitor_functions() {
+        let r = registry(&[("api", 2, true), ("db", 0, false)]);
+        assert!(eval("api", &r));
+        assert!(!eval("db", &r));
+        assert!(eval("failing(api) && !failing(db)", &r));
+        assert!(eval("escalated(api) && !escalated(db)", &r));
+        assert!(eval("level(api) == 2 && level(db) == 0", &r));
+        // unknown monitors are treated as passing at the base level
+        assert!(!eval(
+            "missing || escalated(missing) || level(missing) > 0",
+            &r
+        ));
+        assert!(eval("\"api\" && 'api'", &r));
+    }
+
+    #[test]
+    fn count_with_wildcards() {
+        let r = registry(&[
+            ("web-eu", 1, true),
+            ("web-us", 0, true),
+            ("web-ap", 0, false),
+            ("db-eu", 1, true),
+        ]);
+        assert!(eval("count_failing(web-*) == 2", &r));
+        assert!(eval("count_escalated(web-*) == 1", &r));
+        assert!(eval("count_failing(*-eu) == 2", &r));
+        assert!(eval("count_failing(web-??) == 2", &r));
+        // monitors matched by several patterns are counted once
+        assert!(eval("count_failing(web-*, *-eu) == 3", &r));
+        assert!(eval("count_escalated(web-eu, db-eu, web-eu) == 2", &r));
+        assert!(eval("count_failing(cache-*) == 0", &r));
+        assert!(eval(
+            "count_failing(web-*) >= 2 || count_escalated(*) > 5",
+            &r
+        ));
+    }
+
+    #[test]
+    fn references() {
+        let e = Expr::parse("api && count_failing(web-*, db) > level(cache)").unwrap();
+        assert_eq!(e.references(), ["api", "web-*", "db", "cache"]);
+        assert_eq!(e.names(), ["api", "db", "cache"]);
+    }
+
+    #[test]
+    fn unknown_function() {
+        assert_eq!(
+            Expr::parse("failed(api)").unwrap_err(),
+            "unknown function failed"
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = |src| Expr::parse(src).unwrap_err();
+        assert_eq!(err("(api && db"), "missing closing parenthesis");
+        assert_eq!(err("failing(api"), "missing closing parenthesis");
+        assert_eq!(err("api &&"), "unexpected end of expression");
+        assert_eq!(err(""), "unexpected end of expression");
+        assert_eq!(
+            err("failing(api, db)"),
+            "failing expects a single monitor name"
+        );
+        assert_eq!(err("level()"), "level expects a monitor name");
+        assert_eq!(
+            err("count_failing(1)"),
+            "count_failing expects a monitor name"
+        );
+        assert_eq!(err("\"api"), "unterminated quoted name");
+        assert_eq!(err("api & db"), "unexpected character '&'");
+        assert_eq!(err("api db"), "unexpected Name(\"db\")");
+        assert_eq!(err("api)"), "unexpected RParen");
+        let wildcard = "wildcard web-* is only supported by count_failing and count_escalated";
+        assert_eq!(err("web-*"), wildcard);
+        assert_eq!(err("failing(web-*)"), wildcard);
+        assert_eq!(err("escalated(\"web-*\")"), wildcard);
+        assert_eq!(
+            err("level(web-?)"),
+            "wildcard web-? is only supported by count_failing and count_escalated"
+        );
+    }
+}
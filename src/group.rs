@@ -0,0 +1,98 @@
+//! Monitor groups aggregate alerting across a fleet of similar monitors.
+//!
+//! A group is built as a composite monitor that fails while enough of
+//! its members are failing, with its own levels and reporters. Each
+//! member depends on the group, so while the group is escalated the
+//! members hold their own escalation and reporting and a shared outage
+//! produces a single notification from the group instead of one per
+//! member.
+//!
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::config;
+use crate::matcher::MonitorMatcher;
+use crate::monitor::{LevelArgs, MonitorArgs};
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct MonitorGroupArgs {
+    name: String,
+    /// monitors with a matching tag, may include '*' and '?' wildcards
+    tag: Option<String>,
+    members: Option<Vec<String>>,
+    /// failing members needed for the group to fail, defaults to 1
+    min_failing: Option<usize>,
+    #[serde(deserialize_with = "config::deserialize_duration")]
+    interval: Duration,
+    level: Vec<LevelArgs>,
+}
+
+impl MonitorGroupArgs {
+    /// resolve the members of the group from the configured monitors,
+    /// making each depend on the group, and build the group's monitor
+    pub fn build(self, monitors: &mut [MonitorArgs]) -> Result<MonitorArgs, String> {
+        if monitors.iter().any(|m| m.name == self.name) {
+            return Err(format!(
+                "group {} has the same name as a monitor",
+                self.name
+            ));
+        }
+        let listed = self.members.unwrap_or_default();
+        let matcher = MonitorMatcher {
+            monitor: None,
+            tag: self.tag,
+        };
+        let mut members = Vec::new();
+        for m in monitors.iter_mut() {
+            let tags = m.tags.clone().unwrap_or_default();
+            if listed.contains(&m.name) || matcher.matches(&m.name, &tags) {
+                m.depends_on
+                    .get_or_insert_with(Vec::new)
+                    .push(self.name.clone());
+                m.groups.push((self.name.clone(), self.interval));
+                members.push(m.name.clone());
+            }
+        }
+        if let Some(m) = listed.iter().find(|m| !members.contains(m)) {
+            return Err(format!("group {} has unknown member {}", self.name, m));
+        }
+        if members.is_empty() {
+            return Err(format!("group {} has no members", self.name));
+        }
+        // expression names can be quoted with either quote but not escaped
+        if let Some(m) = members.iter().find(|m| m.contains('"') && m.contains('\'')) {
+            return Err(format!(
+                "group {} member {} can't contain both ' and \" quotes",
+                self.name, m
+            ));
+        }
+        let min_failing = self.min_failing.unwrap_or(1);
+        if min_failing == 0 || min_failing > members.len() {
+            return Err(format!(
+                "group {} min_failing must be between 1 and the number of members",
+                self.name
+            ));
+        }
+        let members: Vec<String> = members.iter().map(|m| quote(m)).collect();
+        Ok(MonitorArgs {
+            name: self.name,
+            interval: self.interval,
+            expression: Some(format!(
+                "count_failing({}) >= {}",
+                members.join(", "),
+                min_failing
+            )),
+            level: self.level,
+            ..Default::default()
+        })
+    }
+}
+
+/// quote a monitor name for use in an expression
+fn quote(name: &str) -> String {
+    if name.contains('"') {
+        format!("'{}'", name)
+    } else {
+        format!("\"{}\"", name)
+    }
+}
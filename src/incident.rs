@@ -0,0 +1,56 @@
+//! Incidents group the results of a monitor from the time it escalates
+//! above its base level until it returns to it, along with a history
+//! of each step taken along the way.
+//!
+//! Incidents are stored in the sqlite database and their id is included
+//! in each monitor result so reporters can reference them.
+//!
+
+/// An incident opened when a monitor escalated above its base level
+#[derive(Clone, Debug)]
+pub struct Incident {
+    pub id: i64,
+    pub monitor: String,
+    pub opened_at: u64,
+    /// highest level reached during the incident
+    pub peak_level: String,
+    pub cleared_at: Option<u64>,
+    pub resolution: Option<String>,
+    pub ack: Option<Acknowledgement>,
+}
+
+/// An acknowledgement of an open incident, which holds further
+/// escalation and notifications until it expires or the incident clears
+#[derive(Clone, Debug)]
+pub struct Acknowledgement {
+    pub time: u64,
+    pub expires_at: Option<u64>,
+    pub by: String,
+    pub comment: String,
+}
+
+impl Acknowledgement {
+    pub fn is_active(&self, now: u64) -> bool {
+        self.expires_at.is_none_or(|e| now < e)
+    }
+}
+
+/// A single step in the lifecycle of an incident
+#[derive(Clone, Debug)]
+pub struct IncidentEvent {
+    pub time: u64,
+    pub kind: String,
+    pub level: String,
+    pub detail: String,
+}
+
+impl IncidentEvent {
+    pub fn new(time: u64, kind: &str, level: &str, detail: String) -> Self {
+        IncidentEvent {
+            time,
+            kind: kind.to_string(),
+            level: level.to_string(),
+            detail,
+        }
+    }
+}
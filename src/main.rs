@@ -1,15 +1,28 @@
+mod cli;
 mod config;
 mod db;
+mod dispatch;
+mod expr;
+mod group;
+mod incident;
+mod matcher;
 mod monitor;
+mod registry;
+mod remediation;
 mod reporters;
+mod silence;
 mod target;
 
 use crate::config::parse_config;
+use crate::reporters::ReporterRegistry;
 
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::Arc;
 
 use clap::Parser;
 use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::Semaphore;
 use tokio_util::sync::CancellationToken;
 use tracing::info;
 use tracing::Level;
@@ -20,18 +33,42 @@ use tokio_util::task::TaskTracker;
 #[command(
     author = "David Miller",
     version = "v0.2.0",
-    about = "Synthehol (easily replicable synthetic monitoring)"
+    about = "Synthehol (easily replicable synthetic monitoring)",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 struct Cli {
-    #[clap(short, long)]
-    config: String,
+    #[clap(short, long, required = true)]
+    config: Option<String>,
+    /// path of the sqlite database used for persistence
+    #[clap(long, global = true, default_value = "./synthehol.db")]
+    db: String,
+    #[command(subcommand)]
+    command: Option<cli::Command>,
 }
 
 #[tokio::main]
 async fn main() {
     let cli_args = Cli::parse();
-    let config =
-        parse_config(&cli_args.config).expect("failed to parse provided configuration path");
+    if let Some(cmd) = cli_args.command {
+        if let Err(e) = cli::run(cmd, &cli_args.db).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let config = parse_config(&cli_args.config.expect("no configuration path provided"))
+        .expect("failed to parse provided configuration path");
+    // named reporter instances, each monitor builds its own copy
+    // from the factory registered for the reporter's type
+    let reporter_registry = ReporterRegistry::default();
+    let reporters = config
+        .reporters()
+        .and_then(|rs| {
+            rs.iter().try_for_each(|r| reporter_registry.validate(r))?;
+            Ok(rs)
+        })
+        .unwrap_or_else(|e| panic!("invalid reporter configuration ({})", e));
 
     let lev = Level::from_str(&config.log_level.unwrap_or(String::from("info")))
         .expect("invalid log level");
@@ -41,7 +78,7 @@ async fn main() {
         .init();
     // dbg!(&config);
     let db = if config.use_db_persistence.unwrap_or(true) {
-        db::SynthDb::new(Some("./synthehol.db"))
+        db::SynthDb::new(Some(&cli_args.db))
             .await
             .expect("failed to open sqlite database")
     } else {
@@ -52,58 +89,152 @@ async fn main() {
     db.initialize_db()
         .await
         .expect("failed to initialize database");
-    let db = Box::leak(Box::new(db));
+    let db: &'static db::SynthDb = Box::leak(Box::new(db));
+    // silences and acknowledgements are written by the cli to the file
+    // database, so it's always used for them and for the incidents
+    // they refer to, even when results aren't persisted
+    let control_db: &'static db::SynthDb = if config.use_db_persistence.unwrap_or(true) {
+        db
+    } else {
+        let control_db = db::SynthDb::new(Some(&cli_args.db))
+            .await
+            .expect("failed to open sqlite database");
+        control_db
+            .initialize_db()
+            .await
+            .expect("failed to initialize database");
+        Box::leak(Box::new(control_db))
+    };
+    let registry = Box::leak(Box::new(registry::Registry::default()));
+
+    // build the shared concurrency limits, these are handed
+    // out to each monitor and held while its target executes
+    let global_limit = config
+        .max_concurrent_targets
+        .map(|n| Arc::new(Semaphore::new(n)));
+    let mut group_limits = HashMap::new();
+    for g in config.concurrency_group.unwrap_or_default() {
+        info!(
+            "concurrency group {} limited to {} targets",
+            g.name, g.max_concurrent
+        );
+        group_limits.insert(g.name, Arc::new(Semaphore::new(g.max_concurrent)));
+    }
+
+    // silences created at runtime, cached and reloaded periodically
+    let silences = Box::leak(Box::new(silence::SilenceCache::default()));
+    silences.refresh(control_db).await;
+
+    // recurring silence windows, registered with each matching monitor
+    let mut windows = Vec::new();
+    for w in config.silence.unwrap_or_default() {
+        windows.push(w.build().expect("invalid silence configuration"));
+    }
+
+    // shared dispatch layer for batching notifications into digests,
+    // given its own instance of each reporter that supports digests
+    let dispatcher = match config.dispatch {
+        Some(d) => {
+            let mut d = d.build(&reporters).expect("invalid dispatch configuration");
+            for r in reporters
+                .iter()
+                .filter(|r| reporter_registry.supports_digest(r))
+            {
+                let rep = reporter_registry
+                    .build(r)
+                    .await
+                    .unwrap_or_else(|e| panic!("failed to initialize reporter ({})", e));
+                d.register_reporter(&r.name, rep);
+            }
+            Some(&*Box::leak(Box::new(d)))
+        }
+        None => None,
+    };
 
     // parse all our monitor configs
     // there's some duplicated work with the reporters being
     // initialized separately for each monitor and copied here
     let mut mons = Vec::new();
-    let monitors = config.monitor.expect("no monitors defined, exiting");
+    let mut monitors = config.monitor.expect("no monitors defined, exiting");
+    // groups are added as composite monitors that their members depend on
+    for g in config.monitor_group.unwrap_or_default() {
+        let group = g
+            .build(&mut monitors)
+            .unwrap_or_else(|e| panic!("invalid monitor group configuration ({})", e));
+        monitors.push(group);
+    }
+    let names: Vec<String> = monitors.iter().map(|m| m.name.clone()).collect();
+    if let Some(cycle) = dependency_cycle(&monitors) {
+        panic!("monitor dependency cycle ({})", cycle.join(" -> "))
+    }
     for m in monitors {
         info!("config parsed for monitor: {}", m.name);
         let mut mon = m.build();
 
         mon.register_db(db);
+        mon.register_incident_db(control_db);
+        mon.register_registry(registry);
+        if let Some(d) = dispatcher {
+            mon.register_dispatcher(d);
+        }
+
+        for d in mon.depends_on.iter() {
+            if !names.contains(d) {
+                panic!("monitor {} depends on unknown monitor {}", mon.name, d)
+            }
+        }
+        for r in mon.references() {
+            if !names.contains(&r) {
+                panic!(
+                    "monitor {} expression references unknown monitor {}",
+                    mon.name, r
+                )
+            }
+        }
 
-        // initialize and register slack reporter if configured, panics on failure
-        if let Some(r) = &config.slack {
-            let slack = r.clone().build();
-            if let Err(e) = slack {
-                panic!("slack reported failed to initilize ({})", e)
+        // group limit is acquired before the global limit so a monitor
+        // waiting on its group doesn't hold a global slot
+        if let Some(g) = mon.concurrency_group.clone() {
+            match group_limits.get(&g) {
+                Some(l) => mon.register_limit(&g, l.clone()),
+                None => panic!(
+                    "monitor {} references unknown concurrency group {}",
+                    mon.name, g
+                ),
             }
-            let slack = Box::new(slack.unwrap());
-            mon.register_reporter("slack", slack);
-            info!("slack reporter registered");
+        }
+        if let Some(l) = &global_limit {
+            mon.register_limit("global", l.clone());
         }
 
-        // initialize and register splunk reporter if configured, panics on failure
-        if let Some(r) = &config.splunk {
-            let splunk = Box::new(r.clone().build());
-            mon.register_reporter("splunk", splunk);
-            info!("splunk reporter registered");
+        mon.register_silences(silences);
+        for w in windows.iter() {
+            if w.matcher.matches(&mon.name, &mon.tags) {
+                mon.register_silence_window(w.clone());
+            }
         }
 
-        // initialize and register pagerduty reporter if configured, panics on failure
-        if let Some(r) = &config.pagerduty {
-            let pagerduty = r.clone().build();
-            if let Err(e) = pagerduty {
-                panic!("pagerduty reported failed to initilize ({})", e)
+        for r in mon.overridden_reporters() {
+            if !reporters.iter().any(|x| x.name.to_lowercase() == r) {
+                panic!("monitor {} overrides unknown reporter {}", mon.name, r)
             }
-            let pagerduty = Box::new(pagerduty.unwrap());
-            mon.register_reporter("pagerduty", pagerduty);
-            info!("pagerduty reporter registered");
         }
 
-        // initialize and register postgresql reporter if configured, panics on failure
-        if let Some(r) = &config.postgresql {
-            let postgresql = Box::new(
-                r.clone()
-                    .build()
+        // initialize and register each reporter instance, along with any
+        // monitor or level specific instances, panics on failure
+        for r in reporters.iter() {
+            let instances = mon
+                .reporter_instances(r, &reporter_registry)
+                .unwrap_or_else(|e| {
+                    panic!("monitor {} invalid reporter override ({})", mon.name, e)
+                });
+            for (name, args) in instances {
+                let rep = reporter_registry
+                    .build(&args)
                     .await
-                    .expect("failed to initialize postgresql reporter"),
-            );
-            mon.register_reporter("postgresql", postgresql);
-            info!("postgresql reporter registered");
+                    .unwrap_or_else(|e| panic!("failed to initialize reporter {} ({})", name, e));
+                mon.register_reporter(&name, rep);
+            }
         }
 
         mons.push(mon);
@@ -114,12 +245,21 @@ async fn main() {
     // a tracker to wait for the tasks to finish before exiting
     let token = CancellationToken::new();
     let tracker = TaskTracker::new();
+    {
+        let cancel = token.clone();
+        let silences = &*silences;
+        tracker.spawn(async move { silences.run(control_db, cancel).await });
+    }
+    if let Some(d) = dispatcher {
+        let cancel = token.clone();
+        tracker.spawn(async move { d.run(cancel).await });
+    }
     for mut m in mons {
         let cancel = token.clone();
         tracker.spawn(async move { m.start(cancel).await });
     }
     let mut sigint = signal(SignalKind::interrupt()).expect("error handling interrupt signal");
-    let mut sigterm = signal(SignalKind::interrupt()).expect("error handling terminate signal");
+    let mut sigterm = signal(SignalKind::terminate()).expect("error handling terminate signal");
 
     tokio::select! {
         _ = sigint.recv() => {
@@ -134,3 +274,45 @@ async fn main() {
     tracker.close();
     tracker.wait().await;
 }
+
+/// find a cycle in the monitor dependencies, which would leave every
+/// monitor in it held by the others, returned as the path around it
+fn dependency_cycle(monitors: &[monitor::MonitorArgs]) -> Option<Vec<String>> {
+    fn visit<'a>(
+        name: &'a str,
+        deps: &HashMap<&'a str, &'a [String]>,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Option<Vec<String>> {
+        if let Some(i) = path.iter().position(|p| *p == name) {
+            let mut cycle: Vec<String> = path[i..].iter().map(|p| p.to_string()).collect();
+            cycle.push(name.to_string());
+            return Some(cycle);
+        }
+        if done.contains(name) {
+            return None;
+        }
+        path.push(name);
+        for d in deps.get(name).copied().unwrap_or_default() {
+            if let Some(c) = visit(d, deps, path, done) {
+                return Some(c);
+            }
+        }
+        path.pop();
+        done.insert(name);
+        None
+    }
+
+    let deps: HashMap<&str, &[String]> = monitors
+        .iter()
+        .map(|m| (m.name.as_str(), m.depends_on.as_deref().unwrap_or_default()))
+        .collect();
+    let mut done: HashSet<&str> = HashSet::new();
+    for m in monitors {
+        let mut path: Vec<&str> = Vec::new();
+        if let Some(c) = visit(&m.name, &deps, &mut path, &mut done) {
+            return Some(c);
+        }
+    }
+    None
+}
@@ -0,0 +1,124 @@
+//! Matching of monitors by name pattern and tag, shared by features
+//! that apply to a selection of monitors rather than a single one.
+//!
+use serde::{Deserialize, Serialize};
+
+/// Selects monitors by a glob pattern on the monitor name and/or a tag,
+/// a monitor must satisfy every field that is set to match
+#[derive(Clone, Deserialize, Serialize, Debug, Default)]
+pub struct MonitorMatcher {
+    pub monitor: Option<String>,
+    pub tag: Option<String>,
+}
+
+impl MonitorMatcher {
+    /// a matcher with neither field set matches nothing, rather than everything
+    pub fn is_empty(&self) -> bool {
+        self.monitor.is_none() && self.tag.is_none()
+    }
+
+    pub fn matches(&self, name: &str, tags: &[String]) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        if let Some(m) = &self.monitor {
+            if !glob_match(m, name) {
+                return false;
+            }
+        }
+        if let Some(t) = &self.tag {
+            if !tags.iter().any(|x| glob_match(t, x)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Simple glob matching where '*' matches any sequence of characters
+/// and '?' matches any single character
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    // position of the last '*' seen and the text position it matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            // backtrack, letting the last '*' consume one more character
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_literal() {
+        assert!(glob_match("web", "web"));
+        assert!(!glob_match("web", "webs"));
+        assert!(!glob_match("webs", "web"));
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "web"));
+    }
+
+    #[test]
+    fn glob_wildcards() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("web-*", "web-1"));
+        assert!(glob_match("web-*", "web-"));
+        assert!(!glob_match("web-*", "db-1"));
+        assert!(glob_match("*-prod", "api-prod"));
+        assert!(glob_match("web-?", "web-1"));
+        assert!(!glob_match("web-?", "web-12"));
+        assert!(glob_match("a**b", "ab"));
+    }
+
+    #[test]
+    fn glob_backtracks() {
+        assert!(glob_match("*a*b", "xaxxab"));
+        assert!(glob_match("*ab", "aab"));
+        assert!(!glob_match("*ab", "aba"));
+        assert!(glob_match("a*b*c", "abbbc"));
+        assert!(!glob_match("a*b*c", "acb"));
+    }
+
+    #[test]
+    fn matcher_fields() {
+        let tags = vec![String::from("prod"), String::from("eu-west")];
+        let empty = MonitorMatcher::default();
+        assert!(!empty.matches("web", &tags));
+        let by_name = MonitorMatcher {
+            monitor: Some(String::from("web*")),
+            tag: None,
+        };
+        assert!(by_name.matches("web-1", &[]));
+        let by_tag = MonitorMatcher {
+            monitor: None,
+            tag: Some(String::from("eu-*")),
+        };
+        assert!(by_tag.matches("db", &tags));
+        assert!(!by_tag.matches("db", &[]));
+        let both = MonitorMatcher {
+            monitor: Some(String::from("web*")),
+            tag: Some(String::from("prod")),
+        };
+        assert!(both.matches("web-1", &tags));
+        assert!(!both.matches("db", &tags));
+        assert!(!both.matches("web-1", &[]));
+    }
+}
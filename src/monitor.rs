@@ -19,57 +19,278 @@
 //! Also defines the Reporter async trait and MonitorResult struct
 //! that can be used to create new reporter modules.
 //!
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::future::Future;
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
 use tokio::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
+use crate::config;
 use crate::db;
-use crate::reporters::Reporter;
+use crate::dispatch::{Admission, Dispatcher};
+use crate::expr::Expr;
+use crate::incident::IncidentEvent;
+use crate::registry::{MonitorStatus, Registry};
+use crate::remediation::{Remediation, RemediationArgs};
+use crate::reporters::{Reporter, ReporterArgs, ReporterRegistry};
+use crate::silence::{SilenceCache, SilenceWindow};
 use crate::target::{Target, TargetArgs};
 
 /// Represents a monitor that executes a target and reports the result
 /// based on the current level
 pub struct Monitor<'a> {
     pub name: String,
-    pub interval: u64,
+    pub tags: Vec<String>,
+    pub interval: Duration,
+    splay: Duration,
+    stagger: bool,
+    overrun: OverrunPolicy,
+    max_overlap: usize,
+    deescalation: Deescalation,
+    exit_levels: HashMap<i32, usize>,
+    exit_level_mode: ExitLevelMode,
+    replicas: usize,
+    quorum: usize,
     levels: Vec<Level>,
     reporters: HashMap<String, Box<dyn Reporter + Send + Sync + 'static>>,
+    reporter_overrides: HashMap<String, toml::Table>,
+    limits: Vec<(String, Arc<Semaphore>)>,
+    silence_windows: Vec<SilenceWindow>,
+    silences: Option<&'a SilenceCache>,
+    pub depends_on: Vec<String>,
+    groups: Vec<(String, Duration)>,
+    pub concurrency_group: Option<String>,
     level_index: usize,
     failure_tally: u64,
     success_tally: u64,
-    target: Target,
+    check: Check,
+    held: bool,
+    streak_start: u64,
+    history: History,
+    flap: Option<FlapDetection>,
+    flapping: bool,
+    last_failed: Option<bool>,
+    last_run: u64,
+    /// when the monitor was first published as failing in the current
+    /// failure streak (ms)
+    failing_since: Option<u64>,
+    /// last notification time by level index and reporter, so
+    /// renotify_every is measured separately at each level
+    last_notified: HashMap<(usize, String), u64>,
+    notified: BTreeSet<String>,
+    incident_id: Option<i64>,
+    acknowledged: bool,
+    clear_pending: bool,
+    /// result that entered a level with a remediation, picked up by
+    /// start() to run it in its own task
+    remediation_due: Option<MonitorResult>,
+    saved_state: Option<db::MonitorState>,
+    saved_reporter_state: HashMap<String, Vec<u8>>,
     running: bool,
     db: &'a db::SynthDb,
+    /// where incidents and their acknowledgements are kept, the file
+    /// database even when results aren't persisted so the cli can ack them
+    incident_db: &'a db::SynthDb,
+    registry: Option<&'a Registry>,
+    dispatcher: Option<&'a Dispatcher>,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug, Default)]
 pub struct MonitorArgs {
     pub name: String,
-    pub interval: u64,
+    pub tags: Option<Vec<String>>,
+    pub depends_on: Option<Vec<String>>,
+    /// groups the monitor is a member of and their intervals, set when
+    /// the groups are built
+    #[serde(skip)]
+    pub groups: Vec<(String, Duration)>,
+    #[serde(deserialize_with = "config::deserialize_duration")]
+    pub interval: Duration,
+    #[serde(default, deserialize_with = "config::deserialize_opt_duration")]
+    pub splay: Option<Duration>,
+    pub stagger: Option<bool>,
+    pub overrun: Option<OverrunPolicy>,
+    pub max_overlap: Option<usize>,
+    pub deescalation: Option<Deescalation>,
+    pub exit_levels: Option<HashMap<String, String>>,
+    pub exit_level_mode: Option<ExitLevelMode>,
+    pub replicas: Option<usize>,
+    pub quorum: Option<usize>,
+    pub concurrency_group: Option<String>,
+    pub flap: Option<FlapArgs>,
+    /// reporter fields overridden for this monitor, by reporter name
+    pub reporter_overrides: Option<HashMap<String, toml::Table>>,
     pub level: Vec<LevelArgs>,
-    pub target: TargetArgs,
+    pub target: Option<TargetArgs>,
+    /// makes this a composite monitor, failing while the expression
+    /// over other monitors is true
+    pub expression: Option<String>,
+}
+
+/// Behavior when a run comes due while the previous run of
+/// the target is still executing
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverrunPolicy {
+    /// drop the run, recording it as skipped
+    Skip,
+    /// run once as soon as the previous run completes, any further
+    /// runs that come due in the meantime are skipped
+    RunOnce,
+    /// start the run alongside the previous one, up to max_overlap
+    /// runs at once, beyond which runs are skipped
+    Overlap,
+}
+
+/// How a monitor leaves an escalated level once its clear
+/// thresholds are met
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Deescalation {
+    /// return straight to the base level
+    Reset,
+    /// step down one level at a time, each level's clear
+    /// thresholds must be met before stepping down again
+    Step,
+}
+
+/// How a level mapped from an exit status is applied
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitLevelMode {
+    /// move straight to the mapped level and hold there while the
+    /// status persists
+    Jump,
+    /// move up to at least the mapped level, the usual escalation
+    /// rules still apply from there
+    Floor,
+}
+
+/// What a monitor checks on each run
+#[derive(Clone, Debug)]
+enum Check {
+    Target(Target),
+    /// expression source and the parsed expression
+    Composite(String, Expr),
 }
 
 impl MonitorArgs {
     pub fn build<'a>(self) -> Monitor<'a> {
+        if self.interval.is_zero() {
+            panic!("monitor {} interval must be greater than zero", self.name)
+        }
         let mut levels = Vec::new();
         for l in self.level.into_iter() {
-            levels.push(l.build())
+            if l.interval.is_some_and(|i| i.is_zero()) {
+                panic!(
+                    "monitor {} level {} interval must be greater than zero",
+                    self.name, l.name
+                )
+            }
+            let name = l.name.clone();
+            match l.build() {
+                Ok(l) => levels.push(l),
+                Err(e) => panic!("monitor {} level {} {}", self.name, name, e),
+            }
+        }
+        let mut exit_levels = HashMap::new();
+        for (status, level) in self.exit_levels.unwrap_or_default() {
+            let Ok(status) = status.parse::<i32>() else {
+                panic!(
+                    "monitor {} exit_levels invalid status ({})",
+                    self.name, status
+                )
+            };
+            match levels.iter().position(|l| l.name == level) {
+                Some(i) => exit_levels.insert(status, i),
+                None => panic!(
+                    "monitor {} exit_levels unknown level ({})",
+                    self.name, level
+                ),
+            };
+        }
+        let replicas = self.replicas.unwrap_or(1);
+        let quorum = self.quorum.unwrap_or(replicas / 2 + 1);
+        if replicas == 0 || quorum == 0 || quorum > replicas {
+            panic!(
+                "monitor {} quorum must be between 1 and the number of replicas",
+                self.name
+            )
+        }
+        let check = match (self.target, self.expression) {
+            (Some(t), None) => Check::Target(t.build()),
+            (None, Some(e)) => match Expr::parse(&e) {
+                Ok(expr) => Check::Composite(e, expr),
+                Err(err) => panic!("monitor {} invalid expression ({})", self.name, err),
+            },
+            _ => panic!(
+                "monitor {} requires either a target or an expression",
+                self.name
+            ),
+        };
+        if replicas > 1 && matches!(check, Check::Composite(..)) {
+            panic!("monitor {} replicas require a target", self.name)
         }
+        let flap = self.flap.map(|f| f.build());
+        let history = History::for_monitor(&levels, flap.as_ref());
         Monitor {
             name: self.name.clone(),
+            tags: self.tags.unwrap_or_default(),
             interval: self.interval,
+            splay: self.splay.unwrap_or(Duration::ZERO),
+            stagger: self.stagger.unwrap_or(false),
+            overrun: self.overrun.unwrap_or(OverrunPolicy::RunOnce),
+            max_overlap: self.max_overlap.unwrap_or(2),
+            deescalation: self.deescalation.unwrap_or(Deescalation::Reset),
+            exit_levels,
+            exit_level_mode: self.exit_level_mode.unwrap_or(ExitLevelMode::Floor),
+            replicas,
+            quorum,
             levels,
             reporters: HashMap::new(),
+            reporter_overrides: self
+                .reporter_overrides
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(k, v)| (k.to_lowercase(), v))
+                .collect(),
+            limits: Vec::new(),
+            silence_windows: Vec::new(),
+            silences: None,
+            depends_on: self.depends_on.unwrap_or_default(),
+            groups: self.groups,
+            concurrency_group: self.concurrency_group,
             level_index: 0,
             failure_tally: 0,
             success_tally: 0,
-            target: self.target.build(),
+            check,
+            held: false,
+            streak_start: 0,
+            history,
+            flap,
+            flapping: false,
+            last_failed: None,
+            last_run: 0,
+            failing_since: None,
+            last_notified: HashMap::new(),
+            notified: BTreeSet::new(),
+            incident_id: None,
+            acknowledged: false,
+            clear_pending: false,
+            remediation_due: None,
+            saved_state: None,
+            saved_reporter_state: HashMap::new(),
             running: false,
-            db: &db::SynthDb { db: None },
+            db: &db::NO_DB,
+            incident_db: &db::NO_DB,
+            registry: None,
+            dispatcher: None,
         }
     }
 }
@@ -85,14 +306,96 @@ impl Monitor<'_> {
         info!("[{}] registered reporter: {}", self.name, name);
     }
 
+    /// The instances of a reporter the monitor needs, by the name they're
+    /// registered under, with the monitor and level overrides merged over
+    /// the reporter's args
+    pub fn reporter_instances(
+        &self,
+        r: &ReporterArgs,
+        registry: &ReporterRegistry,
+    ) -> Result<Vec<(String, ReporterArgs)>, String> {
+        let name = r.name.to_lowercase();
+        let base = match self.reporter_overrides.get(&name) {
+            Some(o) => registry.merge(r, o)?,
+            None => r.clone(),
+        };
+        let mut instances = vec![(name.clone(), base.clone())];
+        for l in self.levels.iter() {
+            if let Some(o) = l.reporter_overrides.get(&name) {
+                instances.push((l.instance(&name), registry.merge(&base, o)?));
+            }
+        }
+        Ok(instances)
+    }
+
+    /// names of the reporters overridden by the monitor or its levels
+    pub fn overridden_reporters(&self) -> Vec<String> {
+        self.reporter_overrides
+            .keys()
+            .chain(self.levels.iter().flat_map(|l| l.reporter_overrides.keys()))
+            .cloned()
+            .collect()
+    }
+
     /// Registers a sqlite database to the monitor for saving/load of state
     /// and the recording of monitor results
     pub fn register_db(&mut self, db: &'static db::SynthDb) {
         self.db = db;
+        db.set_retention(
+            &self.name,
+            self.history.max_runs as u64,
+            self.history.max_age,
+        );
         info!("[{}] registered database", self.name);
     }
 
+    /// Registers the database incidents are recorded in and
+    /// acknowledgements are read from
+    pub fn register_incident_db(&mut self, db: &'static db::SynthDb) {
+        self.incident_db = db;
+        info!("[{}] registered incident database", self.name);
+    }
+
+    /// Registers the shared registry the monitor publishes its status to
+    /// after each run, and reads the status of its dependencies from
+    pub fn register_registry(&mut self, registry: &'static Registry) {
+        self.registry = Some(registry);
+        info!("[{}] registered monitor registry", self.name);
+    }
+
+    /// Registers the shared dispatcher that level notifications are
+    /// admitted through, for batching and rate limiting across monitors
+    pub fn register_dispatcher(&mut self, dispatcher: &'static Dispatcher) {
+        self.dispatcher = Some(dispatcher);
+        info!("[{}] registered notification dispatcher", self.name);
+    }
+
+    /// Registers a concurrency limit that must be acquired before each
+    /// target execution, limits are acquired in the order registered
+    pub fn register_limit(&mut self, name: &str, limit: Arc<Semaphore>) {
+        self.limits.push((name.to_string(), limit));
+        info!("[{}] registered concurrency limit: {}", self.name, name);
+    }
+
+    /// Registers the shared cache of silences created at runtime,
+    /// reports are skipped while a matching silence is active
+    pub fn register_silences(&mut self, silences: &'static SilenceCache) {
+        self.silences = Some(silences);
+        info!("[{}] registered silences", self.name);
+    }
+
+    /// Registers a recurring silence window, reports are skipped while
+    /// the window is active
+    pub fn register_silence_window(&mut self, window: SilenceWindow) {
+        self.silence_windows.push(window);
+        info!("[{}] registered silence window", self.name);
+    }
+
     /// Begin monitoring and reporting loop, shuts down gracefully on cancellation
+    ///
+    /// Runs are scheduled at a fixed rate and the target is executed in a
+    /// separate task, so a run that outlives the interval is handled by
+    /// the configured overrun policy instead of delaying the schedule
     pub async fn start(&mut self, cancel: CancellationToken) {
         info!("[{}] starting", self.name);
         self.running = true;
@@ -102,20 +405,153 @@ impl Monitor<'_> {
         if let Err(e) = self.load_reporters().await {
             info!("[{}] failed to load reporter state ({})", self.name, e);
         }
-        let sleep = tokio::time::sleep(Duration::from_secs(self.interval));
-        tokio::pin!(sleep);
-        let mut duration = self.run().await;
+        if let Err(e) = self.load_history().await {
+            info!("[{}] failed to load result history ({})", self.name, e);
+        }
+        self.publish();
+        let offset = self.startup_offset();
+        if !offset.is_zero() {
+            info!("[{}] staggering first run by {:?}", self.name, offset);
+        }
+        let mut next = Instant::now() + offset;
+        let mut in_flight = JoinSet::new();
+        let mut remediating = JoinSet::new();
+        let mut pending = false;
         while self.running {
             tokio::select! {
                 _ = cancel.cancelled() => { self.stop().await }
-                _ = &mut sleep => {
-                    debug!("[{}] slept {:?}", self.name, duration);
-                    let d = Instant::now() + Duration::from_secs(self.interval) - Duration::from_micros(duration);
-                    sleep.as_mut().reset(d);
-                    duration = self.run().await
+                _ = tokio::time::sleep_until(next) => {
+                    debug!("[{}] run due ({} in progress)", self.name, in_flight.len());
+                    // don't try to catch up on runs missed while the loop
+                    // was busy, that would fire them back to back
+                    next = (next + self.next_interval()).max(Instant::now());
+                    if in_flight.len() < self.max_in_flight() {
+                        in_flight.spawn(self.execution());
+                    } else if self.overrun == OverrunPolicy::RunOnce && !pending {
+                        info!(
+                            "[{}] previous run still in progress, running again once it completes",
+                            self.name
+                        );
+                        pending = true;
+                    } else {
+                        self.skip().await;
+                    }
+                }
+                Some(res) = in_flight.join_next() => {
+                    let interval = self.current_interval();
+                    match res {
+                        Ok(r) => self.run(r).await,
+                        Err(e) => error!("[{}] target execution task failed ({})", self.name, e),
+                    }
+                    if let Some(f) = self.remediation_due.take().and_then(|r| self.remediation(r)) {
+                        remediating.spawn(f);
+                    }
+                    // reschedule if a level change altered the interval
+                    if self.current_interval() != interval {
+                        debug!(
+                            "[{}] interval changed to {:?}",
+                            self.name,
+                            self.current_interval()
+                        );
+                        next = Instant::now() + self.next_interval();
+                    }
+                    if pending {
+                        pending = false;
+                        in_flight.spawn(self.execution());
+                    }
                 }
+                Some(res) = remediating.join_next() => {
+                    let r = match res {
+                        Ok(r) => r,
+                        Err(e) => {
+                            error!("[{}] remediation task failed ({})", self.name, e);
+                            continue;
+                        }
+                    };
+                    self.record_remediation(&r).await;
+                    // confirm a remediation straight away rather than waiting
+                    // for the next scheduled run, within the overrun policy
+                    if !r.rerun {
+                        continue;
+                    }
+                    if in_flight.len() < self.max_in_flight() {
+                        in_flight.spawn(self.execution());
+                        next = Instant::now() + self.next_interval();
+                    } else if self.overrun == OverrunPolicy::RunOnce {
+                        pending = true;
+                        next = Instant::now() + self.next_interval();
+                    } else {
+                        info!("[{}] run already in progress, skipping remediation rerun", self.name);
+                    }
+                }
+            }
+        }
+        // let any runs still in progress finish so their results are recorded
+        while let Some(res) = in_flight.join_next().await {
+            match res {
+                Ok(r) => self.run(r).await,
+                Err(e) => error!("[{}] target execution task failed ({})", self.name, e),
+            }
+            if let Some(f) = self
+                .remediation_due
+                .take()
+                .and_then(|r| self.remediation(r))
+            {
+                remediating.spawn(f);
             }
         }
+        while let Some(res) = remediating.join_next().await {
+            match res {
+                Ok(r) => self.record_remediation(&r).await,
+                Err(e) => error!("[{}] remediation task failed ({})", self.name, e),
+            }
+        }
+    }
+
+    /// number of runs allowed to execute at once under the overrun policy
+    fn max_in_flight(&self) -> usize {
+        match self.overrun {
+            OverrunPolicy::Overlap => self.max_overlap.max(1),
+            OverrunPolicy::Skip | OverrunPolicy::RunOnce => 1,
+        }
+    }
+
+    /// Deterministic delay before the first run, derived from a hash of the
+    /// monitor name so that monitors started together are spread across
+    /// their interval rather than firing at the same instant
+    fn startup_offset(&self) -> Duration {
+        if !self.stagger {
+            return Duration::ZERO;
+        }
+        // FNV-1a, used instead of DefaultHasher so the offset stays
+        // the same across builds and restarts
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for b in self.name.as_bytes() {
+            hash ^= *b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        let interval = (self.current_interval().as_millis() as u64).max(1);
+        Duration::from_millis(hash % interval)
+    }
+
+    /// Interval for the current level, falling back to the monitor interval
+    fn current_interval(&self) -> Duration {
+        self.levels[self.level_index]
+            .interval
+            .unwrap_or(self.interval)
+    }
+
+    /// Time until the next run, randomly adjusted by up to +/- splay
+    /// so the average interval is unchanged
+    fn next_interval(&self) -> Duration {
+        let interval = self.current_interval();
+        if self.splay.is_zero() {
+            return interval;
+        }
+        // never splay by more than the interval itself
+        let splay = self.splay.min(interval);
+        let jitter = rand::thread_rng().gen_range(0..=splay.as_micros() as u64 * 2);
+        interval - splay + Duration::from_micros(jitter)
     }
 
     /// stops the monitor loop
@@ -124,100 +560,536 @@ impl Monitor<'_> {
         self.running = false;
     }
 
-    /// run a single monitor cycle, returning the overall duration
-    async fn run(&mut self) -> u64 {
-        let start = Instant::now();
-        let result = self.execute();
-        let mut out = 0;
+    /// process the result of a single monitor cycle, updating the level
+    /// and dispatching reporters
+    async fn run(&mut self, result: Execution) {
+        let prev_level = self.level_index;
+        let mut reason = String::new();
         match result {
-            Ok(mut r) => {
+            Ok((mut r, outcomes)) => {
+                r.silenced = self.is_silenced(r.start_time);
+                let held_by = self.held_by();
+                self.held = held_by.is_some();
+                let awaiting = match r.status {
+                    0 => None,
+                    _ => self.awaiting_group(r.start_time),
+                };
+                self.history.push(r.start_time, r.status != 0);
+                let flap_changed = self.update_flapping();
+                r.flapping = self.flapping;
+                r.incident_id = self.incident_id;
+                let newly_acked = self.update_acknowledged(r.start_time).await;
+                r.acknowledged = self.acknowledged;
                 if r.status != 0 {
-                    self.incr_failure();
+                    self.incr_failure(r.start_time);
                     let l = &self.levels[self.level_index];
-                    if l.errors_to_escalate <= self.failure_tally {
+                    let mapped = self.exit_levels.get(&r.status).copied();
+                    if let Some(parent) = &held_by {
+                        info!(
+                            "[{}] dependency {} is escalated, holding escalation",
+                            self.name, parent
+                        );
+                    } else if let Some(group) = &awaiting {
+                        info!(
+                            "[{}] group {} hasn't evaluated this failure yet, holding escalation",
+                            self.name, group
+                        );
+                    } else if self.acknowledged {
+                        info!("[{}] incident acknowledged, holding escalation", self.name);
+                    } else if let Some(i) = mapped.filter(|i| *i > self.level_index) {
+                        reason = format!("status {} mapped to level", r.status);
+                        self.escalate_to(i, r.status)
+                    } else if mapped.is_some() && self.exit_level_mode == ExitLevelMode::Jump {
+                        debug!(
+                            "[{}] status {} mapped to a level, holding escalation",
+                            self.name, r.status
+                        );
+                    } else if l.should_escalate(
+                        self.failure_tally,
+                        r.start_time.saturating_sub(self.streak_start),
+                        &self.history,
+                        r.start_time,
+                    ) {
+                        reason = format!("escalated after {} failures", self.failure_tally);
                         self.escalate()
                     }
                 } else {
-                    self.incr_success();
+                    self.incr_success(r.start_time);
                     let l = &self.levels[self.level_index];
-                    if l.successes_to_clear <= self.success_tally {
+                    if l.should_clear(
+                        self.success_tally,
+                        r.start_time.saturating_sub(self.streak_start),
+                    ) {
                         // is this always the right order?
-                        self.clear(&r).await;
-                        self.reset_level()
+                        let recovering = self.level_index > 0 || self.last_failed == Some(true);
+                        // notified reporters are kept so the clear can be
+                        // sent once flapping or the silence ends
+                        if self.flapping {
+                            info!("[{}] monitor flapping, skipping clear", self.name);
+                            if recovering {
+                                self.clear_pending = true;
+                            }
+                        } else if r.silenced {
+                            if recovering {
+                                info!("[{}] monitor silenced, holding clear", self.name);
+                                self.clear_pending = true;
+                            }
+                        } else {
+                            self.clear(&r, recovering).await;
+                        }
+                        reason = format!("cleared after {} successes", self.success_tally);
+                        match self.deescalation {
+                            Deescalation::Reset => self.reset_level(),
+                            Deescalation::Step => self.step_down(r.start_time),
+                        }
                     }
                 }
                 // this needs to be set after we increment the trigger result
                 r.level_name = self.levels[self.level_index].name.clone();
-                self.report(&r).await;
-                match self.db.save_result(r).await {
-                    Ok(_) => {
-                        debug!("[{}] recorded result in local db", self.name)
+                if let Err(e) = self.track_incident(&mut r, prev_level).await {
+                    error!("[{}] failed to update incident ({})", self.name, e);
+                }
+                if self.level_index > prev_level && !r.silenced {
+                    self.remediation_due = Some(r.clone());
+                }
+                if !r.silenced {
+                    // reporters cleared by notify_flapping are skipped
+                    let flap_cleared = flap_changed && held_by.is_none();
+                    self.send_pending_clear(&r, flap_cleared).await;
+                }
+                if r.silenced {
+                    info!("[{}] monitor silenced, skipping report", self.name);
+                } else if held_by.is_some() || awaiting.is_some() {
+                    info!(
+                        "[{}] escalation held, reporting to base level sinks only",
+                        self.name
+                    );
+                    self.record(&r).await;
+                } else {
+                    if newly_acked {
+                        self.acknowledge(&r).await;
+                    }
+                    if flap_changed {
+                        self.notify_flapping(&r).await;
+                    }
+                    if self.flapping {
+                        info!("[{}] monitor flapping, skipping report", self.name);
+                    } else if self.acknowledged {
+                        info!("[{}] incident acknowledged, skipping report", self.name);
+                    } else {
+                        let entered = self.level_index != prev_level;
+                        let changed = entered || self.last_failed != Some(r.status != 0);
+                        self.report(self.level_index, &r, entered, changed).await;
+                    }
+                }
+                self.last_failed = Some(r.status != 0);
+                self.last_run = r.start_time;
+                self.save_outcomes(&r, outcomes).await;
+                let time = r.start_time;
+                let result_id = match self.db.save_result(r).await {
+                    Ok(id) => {
+                        debug!("[{}] recorded result in local db", self.name);
+                        Some(id)
                     }
                     Err(e) => {
                         error!(
                             "[{}] error while attempting to save result in db ({})",
                             self.name, e
-                        )
+                        );
+                        None
                     }
                 };
-                if let Err(e) = self.db.prune_results().await {
-                    error!("[{}] error while pruning result records ({})", self.name, e)
+                // the level changed whether or not its result was saved
+                if self.level_index != prev_level {
+                    self.transition(prev_level, time, reason, result_id).await;
                 }
-                let stop = Instant::now();
-                out = (stop - start).as_micros() as u64;
             }
             Err(e) => {
                 error!(e);
             }
         }
-        // save monitor & reporter state after each run
+        self.publish();
+        // save monitor & reporter state after each run, skipping
+        // the write if nothing has changed
         if let Err(e) = self.save_monitor().await {
             error!("[{}] failed to save monitor state ({})", self.name, e);
         }
         if let Err(e) = self.save_reporters().await {
             error!("[{}] failed to save monitor state ({})", self.name, e);
         }
-        out
     }
 
-    /// a single execution of the monitor target
-    fn execute(&self) -> Result<MonitorResult, String> {
-        info!("[{}] executing target: {}", self.name, self.target.path);
-        let res = self.target.run();
-        match res {
-            Ok(r) => {
-                let mut args = String::new();
-                if let Some(a) = self.target.args.clone() {
-                    args = a.join(",");
+    /// record the individual outcomes of a replicated run
+    async fn save_outcomes(&self, res: &MonitorResult, outcomes: Vec<MonitorResult>) {
+        for mut o in outcomes {
+            o.level_name = res.level_name.clone();
+            o.silenced = res.silenced;
+            o.incident_id = res.incident_id;
+            if let Err(e) = self.db.save_result(o).await {
+                error!(
+                    "[{}] error while attempting to save replica result in db ({})",
+                    self.name, e
+                )
+            }
+        }
+    }
+
+    /// The remediation of a newly entered level, run in its own task by
+    /// start() so a slow command doesn't hold up the schedule
+    fn remediation(
+        &self,
+        res: MonitorResult,
+    ) -> Option<impl Future<Output = Remediated> + Send + 'static> {
+        let l = &self.levels[self.level_index];
+        let remediation = l.remediation.clone()?;
+        info!("[{}] running remediation: {}", self.name, remediation.path);
+        let name = self.name.clone();
+        let level = l.name.clone();
+        let level_index = self.level_index;
+        let incident_id = self.incident_id;
+        Some(async move {
+            let detail = match remediation.run(&res).await {
+                Ok(out) => {
+                    info!(
+                        "[{}] remediation completed with status {:?} ({} μs)",
+                        name, out.status, out.duration
+                    );
+                    let status = match out.status {
+                        Some(s) => s.to_string(),
+                        None => String::from("none"),
+                    };
+                    format!(
+                        "status {} stdout: {} stderr: {}",
+                        status,
+                        out.stdout.trim(),
+                        out.stderr.trim()
+                    )
                 }
-                info!(
-                    "[{}] execution completed for target: {} ({} μs)",
-                    self.name, self.target.path, r.duration
-                );
+                Err(e) => {
+                    error!("[{}] {}", name, e);
+                    e
+                }
+            };
+            Remediated {
+                time: res.start_time,
+                level,
+                level_index,
+                incident_id,
+                detail,
+                rerun: remediation.rerun_target,
+            }
+        })
+    }
+
+    /// record a completed remediation against the incident it ran for
+    async fn record_remediation(&self, r: &Remediated) {
+        let Some(id) = r.incident_id else {
+            return;
+        };
+        let event = IncidentEvent::new(r.time, "remediation", &r.level, r.detail.clone());
+        if let Err(e) = self
+            .incident_db
+            .add_incident_event(id, event, r.level_index)
+            .await
+        {
+            error!("[{}] failed to record remediation ({})", self.name, e);
+        }
+    }
+
+    /// Poll the open incident for an acknowledgement, returning true if
+    /// it was acknowledged since the last run
+    async fn update_acknowledged(&mut self, now: u64) -> bool {
+        let active = match self.incident_id {
+            Some(id) => match self.incident_db.get_incident_ack(id).await {
+                Ok(ack) => ack.is_some_and(|a| a.is_active(now)),
+                Err(e) => {
+                    error!("[{}] failed to check acknowledgement ({})", self.name, e);
+                    self.acknowledged
+                }
+            },
+            None => false,
+        };
+        if active == self.acknowledged {
+            return false;
+        }
+        self.acknowledged = active;
+        if !active {
+            info!(
+                "[{}] acknowledgement expired or removed, resuming escalation",
+                self.name
+            );
+        }
+        active
+    }
+
+    /// Notify reporters that were sent reports during the incident
+    /// that it has been acknowledged
+    async fn acknowledge(&mut self, res: &MonitorResult) {
+        info!("[{}] incident acknowledged", self.name);
+        for k in self.notified.iter() {
+            if let Some(r) = &mut self.reporters.get_mut(k) {
+                r.acknowledge(res).await;
+            }
+        }
+    }
+
+    /// record a level change in the transition log and pass it to
+    /// every registered reporter
+    async fn transition(&mut self, from: usize, time: u64, reason: String, result_id: Option<i64>) {
+        let t = Transition {
+            monitor: self.name.clone(),
+            time,
+            from_level: self.levels[from].name.clone(),
+            to_level: self.levels[self.level_index].name.clone(),
+            reason,
+            result_id: result_id.filter(|id| *id != 0),
+        };
+        for r in self.reporters.values_mut() {
+            r.transition(&t).await;
+        }
+        if let Err(e) = self.db.save_transition(t).await {
+            error!("[{}] failed to save level transition ({})", self.name, e);
+        }
+    }
+
+    /// Open, update or close the monitor's incident after a level change,
+    /// an incident is open while the monitor is above its base level
+    async fn track_incident(
+        &mut self,
+        res: &mut MonitorResult,
+        prev_level: usize,
+    ) -> Result<(), tokio_rusqlite::Error> {
+        if self.level_index == prev_level {
+            return Ok(());
+        }
+        let level = &self.levels[self.level_index].name;
+        let Some(id) = self.incident_id else {
+            if self.level_index == 0 {
+                return Ok(());
+            }
+            let id = self
+                .incident_db
+                .open_incident(
+                    self.name.clone(),
+                    res.start_time,
+                    level.clone(),
+                    self.level_index,
+                )
+                .await?;
+            info!("[{}] opened incident {}", self.name, id);
+            self.incident_id = Some(id);
+            res.incident_id = Some(id);
+            let detail = format!("status {}", res.status);
+            let event = IncidentEvent::new(res.start_time, "opened", level, detail);
+            return self
+                .incident_db
+                .add_incident_event(id, event, self.level_index)
+                .await;
+        };
+        let kind = if self.level_index > prev_level {
+            "escalated"
+        } else if self.level_index > 0 {
+            "stepped_down"
+        } else {
+            "cleared"
+        };
+        let detail = format!("status {}", res.status);
+        let event = IncidentEvent::new(res.start_time, kind, level, detail);
+        self.incident_db
+            .add_incident_event(id, event, self.level_index)
+            .await?;
+        if self.level_index == 0 {
+            let resolution = if self.flapping {
+                "recovered while flapping"
+            } else if res.silenced {
+                "recovered while silenced"
+            } else {
+                "recovered"
+            };
+            self.incident_db
+                .close_incident(id, res.start_time, resolution.to_string())
+                .await?;
+            info!("[{}] closed incident {}", self.name, id);
+            self.incident_id = None;
+        }
+        Ok(())
+    }
 
-                let now = SystemTime::now()
+    /// publish the current status of the monitor to the registry
+    fn publish(&mut self) {
+        let failing = self.last_failed == Some(true);
+        if !failing {
+            self.failing_since = None;
+        } else if self.failing_since.is_none() {
+            self.failing_since = Some(
+                SystemTime::now()
                     .duration_since(UNIX_EPOCH)
                     .expect("System time error")
-                    .as_millis() as u64;
-                Ok(MonitorResult {
-                    name: self.name.clone(),
-                    level_name: String::new(),
-                    start_time: now,
-                    stdout: r.stdout,
-                    stderr: r.stderr,
-                    duration: r.duration,
-                    status: r.status.code().unwrap_or(-1),
-                    target: self.target.path.clone(),
-                    args,
-                    // tags: None,
+                    .as_millis() as u64,
+            );
+        }
+        if let Some(registry) = self.registry {
+            registry.update(
+                &self.name,
+                MonitorStatus {
+                    level_index: self.level_index,
+                    held: self.held,
+                    failing,
+                    last_run: self.last_run,
+                },
+            );
+        }
+    }
+
+    /// find a dependency that is escalated, or is itself held by one
+    /// of its own dependencies, returning its name
+    fn held_by(&self) -> Option<String> {
+        let registry = self.registry?;
+        self.depends_on
+            .iter()
+            .find(|d| registry.get(d).is_some_and(|s| s.escalated() || s.held))
+            .cloned()
+    }
+
+    /// find a group that hasn't evaluated since this monitor started
+    /// failing, so a member holds its escalation until the group has
+    /// had the chance to take over the alert. The hold lasts at most one
+    /// group interval, and only for groups that have published a status
+    fn awaiting_group(&self, now: u64) -> Option<String> {
+        let registry = self.registry?;
+        let since = self.failing_since.unwrap_or(now);
+        self.groups
+            .iter()
+            .find(|(g, interval)| {
+                registry.get(g).is_some_and(|s| {
+                    s.last_run < since && now < since + interval.as_millis() as u64
                 })
+            })
+            .map(|(g, _)| g.clone())
+    }
+
+    /// check for an active silence matching this monitor, either a
+    /// recurring window or a silence created at runtime
+    fn is_silenced(&self, now: u64) -> bool {
+        if let Some(w) = self.silence_windows.iter().find(|w| w.is_active(now)) {
+            debug!("[{}] silenced by window ({})", self.name, w.comment);
+            return true;
+        }
+        let Some(s) = self
+            .silences
+            .and_then(|c| c.matching(&self.name, &self.tags, now))
+        else {
+            return false;
+        };
+        debug!(
+            "[{}] silenced by silence {} ({}: {})",
+            self.name, s.id, s.creator, s.comment
+        );
+        true
+    }
+
+    /// record a skipped run so gaps in the results are visible
+    async fn skip(&mut self) {
+        info!(
+            "[{}] previous run still in progress, skipping run",
+            self.name
+        );
+        let mut r = self.new_result();
+        r.level_name = self.levels[self.level_index].name.clone();
+        r.stdout = String::from("run skipped, previous run still in progress");
+        r.skipped = true;
+        r.status = SKIPPED_STATUS;
+        r.incident_id = self.incident_id;
+        if let Err(e) = self.db.save_result(r).await {
+            error!(
+                "[{}] error while attempting to save skipped result in db ({})",
+                self.name, e
+            )
+        }
+    }
+
+    /// a single execution of the monitor target, as a future that doesn't
+    /// borrow the monitor so it can be spawned alongside the monitor loop
+    fn execution(&self) -> impl Future<Output = Execution> + Send + 'static {
+        let name = self.name.clone();
+        let limits = self.limits.clone();
+        let (replicas, quorum) = (self.replicas, self.quorum);
+        // composite monitors are evaluated straight away, there's
+        // nothing to execute
+        let (target, composite) = match &self.check {
+            Check::Target(t) => (t.clone(), None),
+            Check::Composite(src, expr) => (Target::default(), Some(self.evaluate(src, expr))),
+        };
+        async move {
+            if let Some(res) = composite {
+                return res.map(|r| (r, Vec::new()));
+            }
+            if replicas > 1 {
+                return execute_replicas(&name, target, &limits, replicas, quorum).await;
+            }
+            let (permits, queue_time) = acquire_limits(&name, &limits).await;
+            let res = execute(&name, target).await;
+            drop(permits);
+            res.map(|mut r| {
+                r.queue_time = queue_time;
+                (r, Vec::new())
+            })
+        }
+    }
+
+    /// evaluate a composite monitor's expression against the registry,
+    /// failing while it's true, with the status of each referenced
+    /// monitor as output
+    fn evaluate(&self, src: &str, expr: &Expr) -> Result<MonitorResult, String> {
+        let registry = self
+            .registry
+            .ok_or_else(|| format!("[{}] composite monitor has no registry", self.name))?;
+        let start = Instant::now();
+        let mut res = self.new_result();
+        res.status = expr.is_true(registry) as i32;
+        for pattern in expr.references() {
+            for (name, s) in registry.matching(&pattern) {
+                res.stdout.push_str(&format!(
+                    "{}: level {}, {}\n",
+                    name,
+                    s.level_index,
+                    if s.failing { "failing" } else { "ok" }
+                ));
+            }
+        }
+        res.duration = (Instant::now() - start).as_micros() as u64;
+        debug!("[{}] evaluated {} ({})", self.name, src, res.status);
+        Ok(res)
+    }
+
+    /// an empty result for the monitor's target or expression
+    fn new_result(&self) -> MonitorResult {
+        match &self.check {
+            Check::Target(t) => MonitorResult::new(&self.name, t),
+            Check::Composite(src, _) => {
+                let mut r = MonitorResult::new(&self.name, &Target::default());
+                r.target = String::from("composite");
+                r.args = src.clone();
+                r
             }
-            Err(e) => Err(e),
+        }
+    }
+
+    /// names of the monitors a composite monitor references, without the
+    /// wildcard patterns of its count functions
+    pub fn references(&self) -> Vec<String> {
+        match &self.check {
+            Check::Target(_) => Vec::new(),
+            Check::Composite(_, expr) => expr.names(),
         }
     }
 
     /// Increment failure tally and escalate if needed
-    fn incr_failure(&mut self) {
+    fn incr_failure(&mut self, now: u64) {
+        // streak_start is 0 when loaded from a db created before it was tracked
+        if self.failure_tally == 0 || self.streak_start == 0 {
+            self.streak_start = now;
+        }
         self.success_tally = 0;
         self.failure_tally += 1;
         debug!(
@@ -227,7 +1099,10 @@ impl Monitor<'_> {
     }
 
     /// Increment success tally and clear if needed
-    fn incr_success(&mut self) {
+    fn incr_success(&mut self, now: u64) {
+        if self.success_tally == 0 || self.streak_start == 0 {
+            self.streak_start = now;
+        }
         self.failure_tally = 0;
         // this will keep us from incrementing this indefinitely
         // in cases where the monitor never fails
@@ -253,6 +1128,15 @@ impl Monitor<'_> {
         }
     }
 
+    /// Escalate directly to a level mapped from the exit status
+    fn escalate_to(&mut self, level_index: usize, status: i32) {
+        self.level_index = level_index;
+        info!(
+            "[{}] status {} escalated monitor level (now {})",
+            self.name, status, self.levels[self.level_index].name
+        );
+    }
+
     /// Used to reset level after enough successful monitor runs
     fn reset_level(&mut self) {
         self.level_index = 0;
@@ -262,15 +1146,36 @@ impl Monitor<'_> {
         );
     }
 
+    /// Used to step down a single level after enough successful monitor
+    /// runs, restarting the success streak so the next level's clear
+    /// thresholds apply from here
+    fn step_down(&mut self, now: u64) {
+        if self.level_index == 0 {
+            return;
+        }
+        self.level_index -= 1;
+        self.success_tally = 0;
+        self.streak_start = now;
+        debug!(
+            "[{}] stepped down level (now {})",
+            self.name, self.levels[self.level_index].name
+        );
+    }
+
     /// collect and save the state of all reporters
-    async fn save_reporters(&self) -> Result<(), tokio_rusqlite::Error> {
+    async fn save_reporters(&mut self) -> Result<(), tokio_rusqlite::Error> {
         for (reporter_name, reporter) in self.reporters.iter() {
             if let Some(state) = reporter.get_state() {
+                if self.saved_reporter_state.get(reporter_name) == Some(&state) {
+                    continue;
+                }
                 let name = self.name.clone();
                 debug!("[{}] saving {} reporter state...", self.name, reporter_name);
                 self.db
-                    .save_reporter_state(name, reporter_name.clone(), state)
+                    .save_reporter_state(name, reporter_name.clone(), state.clone())
                     .await?;
+                self.saved_reporter_state
+                    .insert(reporter_name.clone(), state);
                 debug!(
                     "[{}] {} reporter state save completed",
                     self.name, reporter_name
@@ -285,23 +1190,33 @@ impl Monitor<'_> {
             let mon = self.name.clone();
             let rep = reporter_name.clone();
             if let Some(state) = self.db.get_reporter_state(mon, rep).await? {
-                reporter.load_state(state);
+                reporter.load_state(state.clone());
+                self.saved_reporter_state
+                    .insert(reporter_name.clone(), state);
             }
         }
         Ok(())
     }
 
-    async fn save_monitor(&self) -> Result<(), tokio_rusqlite::Error> {
+    async fn save_monitor(&mut self) -> Result<(), tokio_rusqlite::Error> {
+        let state = db::MonitorState {
+            level_index: self.level_index,
+            failure_tally: self.failure_tally,
+            success_tally: self.success_tally,
+            streak_start: self.streak_start,
+            flapping: self.flapping,
+            notified: self.notified.iter().cloned().collect(),
+            incident_id: self.incident_id,
+            acknowledged: self.acknowledged,
+            clear_pending: self.clear_pending,
+        };
+        if self.saved_state.as_ref() == Some(&state) {
+            return Ok(());
+        }
         let name = self.name.clone();
         debug!("[{}] saving monitor state...", self.name);
-        self.db
-            .save_monitor_state(
-                name,
-                self.level_index,
-                self.failure_tally,
-                self.success_tally,
-            )
-            .await?;
+        self.db.save_monitor_state(name, state.clone()).await?;
+        self.saved_state = Some(state);
         debug!("[{}] monitor state save completed", self.name);
         Ok(())
     }
@@ -311,65 +1226,628 @@ impl Monitor<'_> {
         debug!("[{}] load monitor state...", self.name);
         let state = self.db.get_monitor_state(self.name.clone()).await?;
         if let Some(s) = state {
-            self.level_index = s.0;
-            self.failure_tally = s.1;
-            self.success_tally = s.2;
+            // the levels may have changed since the state was saved
+            self.level_index = s.level_index.min(self.levels.len() - 1);
+            self.failure_tally = s.failure_tally;
+            self.success_tally = s.success_tally;
+            self.streak_start = s.streak_start;
+            self.flapping = s.flapping;
+            self.notified = s.notified.iter().cloned().collect();
+            self.incident_id = s.incident_id;
+            self.acknowledged = s.acknowledged;
+            self.clear_pending = s.clear_pending;
+            self.saved_state = Some(s);
         }
         debug!("[{}] monitor state load completed", self.name);
         Ok(())
     }
 
-    /// Dispatch all reporters based on current level
-    async fn report(&mut self, res: &MonitorResult) {
-        let l = &self.levels[self.level_index];
+    /// load recent results from the configured db for windowed escalation
+    async fn load_history(&mut self) -> Result<(), tokio_rusqlite::Error> {
+        if self.history.is_disabled() {
+            return Ok(());
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("System time error")
+            .as_millis() as u64;
+        let results = self
+            .db
+            .get_recent_results(
+                self.name.clone(),
+                self.history.max_runs,
+                now.saturating_sub(self.history.max_age),
+            )
+            .await?;
+        debug!(
+            "[{}] loaded {} results into history",
+            self.name,
+            results.len()
+        );
+        for (start_time, status) in results {
+            self.history.push(start_time, status != 0);
+        }
+        Ok(())
+    }
+
+    /// Dispatch all reporters of a level, subject to each
+    /// reporter's notification mode given whether this run entered the
+    /// level or changed the monitor's level or status
+    async fn report(
+        &mut self,
+        level_index: usize,
+        res: &MonitorResult,
+        entered: bool,
+        changed: bool,
+    ) {
+        let l = &self.levels[level_index];
+        for k in l.reporters.iter() {
+            let k_l = k.clone().to_lowercase();
+            // renotifying only repeats alerts, passing runs aren't repeated
+            let last = self.last_notified.get(&(level_index, k_l.clone()));
+            let alerting = res.status != 0 || self.level_index > 0;
+            let due = match l.notify_mode(&k_l) {
+                NotifyMode::EveryRun => true,
+                NotifyMode::OnEnterLevel => entered,
+                NotifyMode::OnChange => changed,
+            } || alerting
+                && l.renotify_every.zip(last).is_some_and(|(d, last)| {
+                    d.as_millis() as u64 <= res.start_time.saturating_sub(*last)
+                });
+            if !due {
+                debug!("[{}] {} notification not due, skipping", self.name, k);
+                continue;
+            }
+            // overridden instances aren't batched, as digests are sent
+            // through the reporter's global configuration
+            let key = l.instance(&k_l);
+            let batch = key == k_l && !self.reporter_overrides.contains_key(&k_l);
+            if let Some(r) = &mut self.reporters.get_mut(&key) {
+                // only failures go through the dispatcher, passing results
+                // to every-run sinks aren't alerts and are always sent
+                let admission = match self.dispatcher {
+                    Some(d) if res.status != 0 => d.admit(&k_l, res, batch),
+                    _ => Admission::Send,
+                };
+                match admission {
+                    Admission::Send => r.report(res).await,
+                    Admission::Batched => {
+                        // the digest is sent through the dispatcher's own
+                        // instance, but the failure is still cleared through
+                        // this one on recovery
+                        debug!("[{}] {} notification held for digest", self.name, k);
+                        self.notified.insert(key);
+                        continue;
+                    }
+                    Admission::Dropped => {
+                        warn!(
+                            "[{}] {} rate limit reached, notification dropped",
+                            self.name, k
+                        );
+                        continue;
+                    }
+                }
+                self.last_notified
+                    .insert((level_index, k_l), res.start_time);
+                if res.status != 0 {
+                    self.notified.insert(key);
+                }
+            }
+        }
+    }
+
+    /// send a held result to the base level's every-run sinks, reporters
+    /// that alert aren't sent anything until the hold ends
+    async fn record(&mut self, res: &MonitorResult) {
+        let l = &self.levels[0];
         for k in l.reporters.iter() {
+            let k_l = k.to_lowercase();
+            if l.notify_mode(&k_l) != NotifyMode::EveryRun {
+                continue;
+            }
+            if let Some(r) = self.reporters.get_mut(&l.instance(&k_l)) {
+                if !r.alerts() {
+                    r.report(res).await;
+                }
+            }
+        }
+    }
+
+    /// update the flapping state from the weighted state change percentage
+    /// of recent results, returning true if the monitor started or stopped
+    /// flapping
+    fn update_flapping(&mut self) -> bool {
+        let Some(f) = &self.flap else {
+            return false;
+        };
+        let pct = self.history.state_change_percent(f.window);
+        let flapping = if self.flapping {
+            pct >= f.low
+        } else {
+            pct > f.high
+        };
+        if flapping == self.flapping {
+            return false;
+        }
+        info!(
+            "[{}] monitor {} flapping ({:.1}% state change)",
+            self.name,
+            if flapping { "started" } else { "stopped" },
+            pct
+        );
+        self.flapping = flapping;
+        true
+    }
+
+    /// Notify flap reporters that the monitor started flapping, or
+    /// clear them once it stops
+    async fn notify_flapping(&mut self, res: &MonitorResult) {
+        let Some(f) = &self.flap else {
+            return;
+        };
+        for k in f.reporters.iter() {
             let k_l = k.clone().to_lowercase();
             if let Some(r) = &mut self.reporters.get_mut(&k_l) {
-                r.report(res).await;
+                if self.flapping {
+                    r.flap(res).await;
+                } else {
+                    r.clear(res).await;
+                }
+            }
+        }
+    }
+
+    /// Clear the reporters notified during an incident that recovered while
+    /// its clear was held, unless the monitor has escalated again since, in
+    /// which case they're cleared when the new incident recovers. Flap
+    /// reporters are skipped if they're being cleared as flapping stops
+    async fn send_pending_clear(&mut self, res: &MonitorResult, flap_cleared: bool) {
+        if !self.clear_pending || self.flapping {
+            return;
+        }
+        self.clear_pending = false;
+        if self.level_index > 0 {
+            return;
+        }
+        for k in std::mem::take(&mut self.notified) {
+            let is_flap_reporter = self
+                .flap
+                .as_ref()
+                .is_some_and(|f| f.reporters.iter().any(|x| x.to_lowercase() == k));
+            if flap_cleared && is_flap_reporter {
+                continue;
+            }
+            if let Some(r) = &mut self.reporters.get_mut(&k) {
+                info!("[{}] sending held clear to {}", self.name, k);
+                r.clear(res).await;
             }
         }
     }
 
-    /// Clear all reporters based on current level
-    async fn clear(&mut self, res: &MonitorResult) {
+    /// Clear all reporters based on current level, reporters that aren't
+    /// notified on every run are only cleared when recovering from a failure.
+    /// Any other reporter notified during the incident is cleared once the
+    /// monitor returns to the base level
+    async fn clear(&mut self, res: &MonitorResult, recovering: bool) {
         let l = &self.levels[self.level_index];
         for k in l.reporters.iter() {
             let k_l = k.clone().to_lowercase();
-            if let Some(r) = &mut self.reporters.get_mut(&k_l) {
+            if !recovering && l.notify_mode(&k_l) != NotifyMode::EveryRun {
+                continue;
+            }
+            let key = l.instance(&k_l);
+            if let Some(r) = &mut self.reporters.get_mut(&key) {
                 r.clear(res).await;
             }
+            self.notified.remove(&key);
+        }
+        // reporters notified earlier in the incident may not belong to
+        // this level, clear them too once the monitor fully recovers
+        if recovering && (self.deescalation == Deescalation::Reset || self.level_index <= 1) {
+            for k in std::mem::take(&mut self.notified) {
+                if let Some(r) = &mut self.reporters.get_mut(&k) {
+                    debug!(
+                        "[{}] clearing {} notified earlier in incident",
+                        self.name, k
+                    );
+                    r.clear(res).await;
+                }
+            }
         }
     }
 }
 
+/// wait for a permit from each concurrency limit, returning the
+/// held permits and the time spent queued (μs)
+async fn acquire_limits(
+    name: &str,
+    limits: &[(String, Arc<Semaphore>)],
+) -> (Vec<OwnedSemaphorePermit>, u64) {
+    let start = Instant::now();
+    let mut permits = Vec::new();
+    for (limit_name, limit) in limits.iter() {
+        match limit.clone().acquire_owned().await {
+            Ok(p) => permits.push(p),
+            Err(e) => error!(
+                "[{}] failed to acquire {} concurrency permit ({})",
+                name, limit_name, e
+            ),
+        }
+    }
+    let queue_time = (Instant::now() - start).as_micros() as u64;
+    if !limits.is_empty() {
+        debug!("[{}] queued for {} μs", name, queue_time);
+    }
+    (permits, queue_time)
+}
+
+/// run the target once per replica concurrently, returning a result
+/// carrying the verdict of the vote along with each replica's outcome.
+/// Each replica holds its own concurrency permits while it runs. The
+/// verdict is a failure when at least `quorum` replicas failed
+async fn execute_replicas(
+    name: &str,
+    target: Target,
+    limits: &[(String, Arc<Semaphore>)],
+    replicas: usize,
+    quorum: usize,
+) -> Result<(MonitorResult, Vec<MonitorResult>), String> {
+    let mut set = JoinSet::new();
+    for i in 0..replicas {
+        let name = name.to_string();
+        let target = target.clone();
+        let limits = limits.to_vec();
+        set.spawn(async move {
+            let (permits, queue_time) = acquire_limits(&name, &limits).await;
+            // a replica that can't run counts as a failed vote
+            let mut r = execute(&name, target.clone()).await.unwrap_or_else(|e| {
+                let mut r = MonitorResult::new(&name, &target);
+                r.stderr = e;
+                r.status = -1;
+                r
+            });
+            drop(permits);
+            r.queue_time = queue_time;
+            r.replica = Some(i);
+            r
+        });
+    }
+    let mut outcomes = Vec::new();
+    while let Some(r) = set.join_next().await {
+        match r {
+            Ok(r) => outcomes.push(r),
+            Err(e) => error!("[{}] replica execution task failed ({})", name, e),
+        }
+    }
+    outcomes.sort_by_key(|r| r.replica);
+    let failed = outcomes.iter().filter(|r| r.status != 0).count();
+    let passed = outcomes.len() - failed;
+    let verdict = failed >= quorum;
+    info!(
+        "[{}] {} of {} replicas failed (quorum {})",
+        name, failed, replicas, quorum
+    );
+    let mut res = outcomes
+        .iter()
+        .find(|r| (r.status != 0) == verdict)
+        .ok_or_else(|| String::from("no replica outcomes"))?
+        .clone();
+    res.replica = None;
+    // the run waited for as long as its slowest replica to start
+    res.queue_time = outcomes.iter().map(|r| r.queue_time).max().unwrap_or(0);
+    res.votes = Some(Votes { passed, failed });
+    Ok((res, outcomes))
+}
+
+/// a single execution of the target, run on the blocking thread pool
+async fn execute(name: &str, target: Target) -> Result<MonitorResult, String> {
+    info!("[{}] executing target: {}", name, target.path);
+    let mut res = MonitorResult::new(name, &target);
+    let r = tokio::task::spawn_blocking(move || target.run())
+        .await
+        .map_err(|e| format!("target execution task failed ({})", e))??;
+    info!(
+        "[{}] execution completed for target: {} ({} μs)",
+        name, res.target, r.duration
+    );
+    res.stdout = r.stdout;
+    res.stderr = r.stderr;
+    res.duration = r.duration;
+    res.status = r.status.code().unwrap_or(-1);
+    Ok(res)
+}
+
 /// Levels contain a list of reporter names that will be attempted
 /// after the parent monitor target executes, and optionally a number
 /// of allowed failures before escalation should be tried
 #[derive(Debug)]
 struct Level {
     name: String,
-    errors_to_escalate: u64,
-    successes_to_clear: u64,
+    errors_to_escalate: Option<u64>,
+    successes_to_clear: Option<u64>,
+    escalate_after: Option<Duration>,
+    clear_after: Option<Duration>,
+    window: Option<(u64, usize)>,
+    failure_rate: Option<(f64, Duration)>,
+    interval: Option<Duration>,
+    notify: NotifyMode,
+    renotify_every: Option<Duration>,
+    reporter_notify: HashMap<String, NotifyMode>,
+    reporter_overrides: HashMap<String, toml::Table>,
+    remediation: Option<Remediation>,
     reporters: Vec<String>,
 }
 
+/// When the reporters of a level are sent a report
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyMode {
+    /// on every run while at the level
+    EveryRun,
+    /// only on the run that entered the level
+    OnEnterLevel,
+    /// when the level or the pass/fail status changes
+    OnChange,
+}
+
 #[derive(Clone, Deserialize, Debug)]
 pub struct LevelArgs {
     name: String,
     errors_to_escalate: Option<u64>,
     successes_to_clear: Option<u64>,
+    #[serde(default, deserialize_with = "config::deserialize_opt_duration")]
+    escalate_after: Option<Duration>,
+    #[serde(default, deserialize_with = "config::deserialize_opt_duration")]
+    clear_after: Option<Duration>,
+    window_failures: Option<u64>,
+    window_runs: Option<usize>,
+    failure_rate: Option<f64>,
+    #[serde(default, deserialize_with = "config::deserialize_opt_duration")]
+    rate_window: Option<Duration>,
+    #[serde(default, deserialize_with = "config::deserialize_opt_duration")]
+    interval: Option<Duration>,
+    notify: Option<NotifyMode>,
+    #[serde(default, deserialize_with = "config::deserialize_opt_duration")]
+    renotify_every: Option<Duration>,
+    reporter_notify: Option<HashMap<String, NotifyMode>>,
+    /// reporter fields overridden for this level, by reporter name
+    reporter_overrides: Option<HashMap<String, toml::Table>>,
+    remediation: Option<RemediationArgs>,
     reporters: Vec<String>,
 }
 
 impl LevelArgs {
-    fn build(self) -> Level {
-        Level {
+    fn build(self) -> Result<Level, String> {
+        let window = match (self.window_failures, self.window_runs) {
+            (Some(k), Some(n)) if k as usize <= n => Some((k, n)),
+            (Some(_), Some(_)) => {
+                return Err(String::from(
+                    "window_failures must not be greater than window_runs",
+                ))
+            }
+            (None, None) => None,
+            _ => {
+                return Err(String::from(
+                    "window_failures and window_runs must be set together",
+                ))
+            }
+        };
+        let failure_rate = match (self.failure_rate, self.rate_window) {
+            (Some(r), Some(w)) => Some((r, w)),
+            (None, None) => None,
+            _ => {
+                return Err(String::from(
+                    "failure_rate and rate_window must be set together",
+                ))
+            }
+        };
+        let other_escalation =
+            self.escalate_after.is_some() || window.is_some() || failure_rate.is_some();
+        let remediation = match self.remediation {
+            Some(r) => Some(r.build()?),
+            None => None,
+        };
+        Ok(Level {
             name: self.name,
-            errors_to_escalate: self.errors_to_escalate.unwrap_or(1),
-            successes_to_clear: self.successes_to_clear.unwrap_or(1),
+            // count-based thresholds default to 1 unless another
+            // threshold is configured in their place
+            errors_to_escalate: self.errors_to_escalate.or((!other_escalation).then_some(1)),
+            successes_to_clear: self
+                .successes_to_clear
+                .or(self.clear_after.is_none().then_some(1)),
+            escalate_after: self.escalate_after,
+            clear_after: self.clear_after,
+            window,
+            failure_rate,
+            interval: self.interval,
+            notify: self.notify.unwrap_or(NotifyMode::EveryRun),
+            renotify_every: self.renotify_every,
+            reporter_notify: self
+                .reporter_notify
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(k, v)| (k.to_lowercase(), v))
+                .collect(),
+            reporter_overrides: self
+                .reporter_overrides
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(k, v)| (k.to_lowercase(), v))
+                .collect(),
+            remediation,
             reporters: self.reporters,
+        })
+    }
+}
+
+impl Level {
+    /// name of the reporter instance used by the level, levels that
+    /// override a reporter's fields have their own instance
+    fn instance(&self, reporter: &str) -> String {
+        if self.reporter_overrides.contains_key(reporter) {
+            format!("{}@{}", reporter, self.name.to_lowercase())
+        } else {
+            reporter.to_string()
         }
     }
+
+    /// notification mode for a reporter, overriding the level's mode
+    fn notify_mode(&self, reporter: &str) -> NotifyMode {
+        self.reporter_notify
+            .get(reporter)
+            .copied()
+            .unwrap_or(self.notify)
+    }
+
+    /// check the failure streak and recent history against the
+    /// escalation thresholds, escalating when any threshold is met
+    fn should_escalate(&self, failures: u64, streak: u64, history: &History, now: u64) -> bool {
+        self.errors_to_escalate.is_some_and(|e| e <= failures)
+            || self
+                .escalate_after
+                .is_some_and(|d| d.as_millis() as u64 <= streak)
+            || self
+                .window
+                .is_some_and(|(k, n)| k <= history.failures_in_last(n))
+            || self.failure_rate.is_some_and(|(rate, window)| {
+                history
+                    .failure_rate_since(now.saturating_sub(window.as_millis() as u64))
+                    .is_some_and(|r| r > rate)
+            })
+    }
+
+    /// check the success streak against the clear thresholds,
+    /// clearing when either threshold is met
+    fn should_clear(&self, successes: u64, streak: u64) -> bool {
+        self.successes_to_clear.is_some_and(|s| s <= successes)
+            || self
+                .clear_after
+                .is_some_and(|d| d.as_millis() as u64 <= streak)
+    }
+}
+
+/// Nagios-style flap detection, a monitor starts flapping when the weighted
+/// state change percentage of recent results rises above the high threshold
+/// and stops once it falls below the low threshold
+#[derive(Debug)]
+struct FlapDetection {
+    window: usize,
+    high: f64,
+    low: f64,
+    reporters: Vec<String>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct FlapArgs {
+    window: Option<usize>,
+    high: Option<f64>,
+    low: Option<f64>,
+    reporters: Vec<String>,
+}
+
+impl FlapArgs {
+    fn build(self) -> FlapDetection {
+        FlapDetection {
+            window: self.window.unwrap_or(21),
+            high: self.high.unwrap_or(50.0),
+            low: self.low.unwrap_or(25.0),
+            reporters: self.reporters,
+        }
+    }
+}
+
+/// Recent pass/fail outcomes kept for windowed escalation rules, holding
+/// at least the last max_runs results and every result within max_age
+#[derive(Debug, Default)]
+struct History {
+    entries: VecDeque<(u64, bool)>,
+    max_runs: usize,
+    max_age: u64,
+}
+
+impl History {
+    /// size the history for the largest window used by any level
+    /// or by flap detection
+    fn for_monitor(levels: &[Level], flap: Option<&FlapDetection>) -> Self {
+        History {
+            entries: VecDeque::new(),
+            max_runs: levels
+                .iter()
+                .filter_map(|l| l.window.map(|w| w.1))
+                .chain(flap.map(|f| f.window))
+                .max()
+                .unwrap_or(0),
+            max_age: levels
+                .iter()
+                .filter_map(|l| l.failure_rate.map(|r| r.1.as_millis() as u64))
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// no level uses a windowed rule, so nothing needs to be kept
+    fn is_disabled(&self) -> bool {
+        self.max_runs == 0 && self.max_age == 0
+    }
+
+    fn push(&mut self, time: u64, failed: bool) {
+        if self.is_disabled() {
+            return;
+        }
+        self.entries.push_back((time, failed));
+        while self.entries.len() > self.max_runs
+            && self
+                .entries
+                .front()
+                .is_some_and(|e| e.0 < time.saturating_sub(self.max_age))
+        {
+            self.entries.pop_front();
+        }
+    }
+
+    /// number of failures in the most recent n results
+    fn failures_in_last(&self, n: usize) -> u64 {
+        self.entries.iter().rev().take(n).filter(|e| e.1).count() as u64
+    }
+
+    /// weighted percentage of state changes across the most recent n
+    /// results, with later changes weighted more heavily (0.8 to 1.2),
+    /// 0 until n results have been recorded
+    fn state_change_percent(&self, n: usize) -> f64 {
+        if n < 2 || self.entries.len() < n {
+            return 0.0;
+        }
+        let recent: Vec<bool> = self
+            .entries
+            .iter()
+            .rev()
+            .take(n)
+            .rev()
+            .map(|e| e.1)
+            .collect();
+        let changes = recent.len() - 1;
+        let mut total = 0.0;
+        for i in 0..changes {
+            if recent[i] != recent[i + 1] {
+                total += if changes > 1 {
+                    0.8 + 0.4 * i as f64 / (changes - 1) as f64
+                } else {
+                    1.0
+                };
+            }
+        }
+        total * 100.0 / changes as f64
+    }
+
+    /// percentage of results since the given time that failed
+    fn failure_rate_since(&self, since: u64) -> Option<f64> {
+        let recent: Vec<_> = self.entries.iter().filter(|e| e.0 >= since).collect();
+        if recent.is_empty() {
+            return None;
+        }
+        let failed = recent.iter().filter(|e| e.1).count();
+        Some(failed as f64 * 100.0 / recent.len() as f64)
+    }
 }
 
 /// Result returned from a single execution of a monitor target
@@ -383,5 +1861,395 @@ pub struct MonitorResult {
     pub stdout: String,
     pub stderr: String,
     pub duration: u64,
+    pub queue_time: u64,
     pub status: i32,
+    pub skipped: bool,
+    pub silenced: bool,
+    pub flapping: bool,
+    /// the incident open when the result was reported
+    pub incident_id: Option<i64>,
+    pub acknowledged: bool,
+    /// replica number, set on the individual outcomes of a replicated run
+    pub replica: Option<usize>,
+    /// vote breakdown, set on the aggregate result of a replicated run
+    pub votes: Option<Votes>,
+}
+
+/// Pass/fail vote breakdown of the replicas of a single run
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct Votes {
+    pub passed: usize,
+    pub failed: usize,
+}
+
+/// result of a run, along with the individual outcomes of each
+/// replica when the target is replicated
+type Execution = Result<(MonitorResult, Vec<MonitorResult>), String>;
+
+/// status recorded for skipped runs, outside the range of exit statuses
+/// so a skipped run can't be mistaken for a successful one
+const SKIPPED_STATUS: i32 = -2;
+
+/// A completed remediation and the incident it was run for
+struct Remediated {
+    time: u64,
+    level: String,
+    level_index: usize,
+    incident_id: Option<i64>,
+    detail: String,
+    rerun: bool,
+}
+
+/// A change in the level of a monitor and the result that caused it
+#[derive(Debug, Serialize, Clone)]
+pub struct Transition {
+    pub monitor: String,
+    pub time: u64,
+    pub from_level: String,
+    pub to_level: String,
+    pub reason: String,
+    pub result_id: Option<i64>,
+}
+
+impl MonitorResult {
+    /// an empty result for the given monitor target, starting now
+    fn new(name: &str, target: &Target) -> Self {
+        let mut args = String::new();
+        if let Some(a) = target.args.clone() {
+            args = a.join(",");
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("System time error")
+            .as_millis() as u64;
+        MonitorResult {
+            name: name.to_string(),
+            level_name: String::new(),
+            start_time: now,
+            target: target.path.clone(),
+            args,
+            stdout: String::new(),
+            stderr: String::new(),
+            duration: 0,
+            queue_time: 0,
+            status: 0,
+            skipped: false,
+            silenced: false,
+            flapping: false,
+            incident_id: None,
+            acknowledged: false,
+            replica: None,
+            votes: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatch::DispatchArgs;
+    use crate::incident::Acknowledgement;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// records the notifications sent to it
+    struct Recorder(Arc<Mutex<Vec<&'static str>>>);
+
+    #[async_trait]
+    impl Reporter for Recorder {
+        async fn report(&mut self, _: &MonitorResult) {
+            self.0.lock().unwrap().push("report");
+        }
+        async fn clear(&mut self, _: &MonitorResult) {
+            self.0.lock().unwrap().push("clear");
+        }
+        async fn flap(&mut self, _: &MonitorResult) {
+            self.0.lock().unwrap().push("flap");
+        }
+        fn get_state(&self) -> Option<Vec<u8>> {
+            None
+        }
+        fn load_state(&mut self, _: Vec<u8>) {}
+    }
+
+    fn recorder(mon: &mut Monitor, name: &str) -> Arc<Mutex<Vec<&'static str>>> {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        mon.register_reporter(name, Box::new(Recorder(calls.clone())));
+        calls
+    }
+
+    fn monitor(src: &str) -> Monitor<'static> {
+        toml::from_str::<MonitorArgs>(src).unwrap().build()
+    }
+
+    fn calls(rec: &Arc<Mutex<Vec<&'static str>>>) -> Vec<&'static str> {
+        rec.lock().unwrap().clone()
+    }
+
+    async fn run_status(mon: &mut Monitor<'_>, run: u64, status: i32) {
+        let mut r = mon.new_result();
+        r.start_time += run * 1000;
+        r.status = status;
+        mon.run(Ok((r, Vec::new()))).await;
+    }
+
+    #[tokio::test]
+    async fn clear_held_while_flapping() {
+        let mut mon = monitor(
+            r#"
+            name = "web"
+            interval = 1
+            flap = { window = 4, reporters = ["flap"] }
+            [target]
+            path = "true"
+            [[level]]
+            name = "info"
+            reporters = []
+            [[level]]
+            name = "alert"
+            successes_to_clear = 3
+            reporters = ["page"]
+            "#,
+        );
+        let page = recorder(&mut mon, "page");
+        let flap = recorder(&mut mon, "flap");
+
+        // escalate, then start flapping while escalated
+        for (run, status) in [(1, 1), (2, 0), (3, 1), (4, 0)] {
+            run_status(&mut mon, run, status).await;
+        }
+        assert_eq!(mon.level_index, 1);
+        assert!(mon.flapping);
+        assert_eq!(*flap.lock().unwrap(), ["flap"]);
+
+        // recover while flapping, the clear is held
+        run_status(&mut mon, 5, 0).await;
+        run_status(&mut mon, 6, 0).await;
+        assert_eq!(mon.level_index, 0);
+        assert!(mon.flapping);
+        assert!(mon.clear_pending);
+        assert!(!page.lock().unwrap().contains(&"clear"));
+
+        // stop flapping, every notified reporter is cleared once
+        run_status(&mut mon, 7, 0).await;
+        assert!(!mon.flapping);
+        assert!(!mon.clear_pending);
+        assert!(mon.notified.is_empty());
+        assert_eq!(page.lock().unwrap().last(), Some(&"clear"));
+        assert_eq!(
+            page.lock()
+                .unwrap()
+                .iter()
+                .filter(|c| **c == "clear")
+                .count(),
+            1
+        );
+        assert_eq!(*flap.lock().unwrap(), ["flap", "clear"]);
+    }
+
+    const ESCALATING: &str = r#"
+        name = "web"
+        interval = 1
+        [target]
+        path = "true"
+        [[level]]
+        name = "info"
+        reporters = []
+        [[level]]
+        name = "alert"
+        reporters = ["page"]
+        "#;
+
+    #[tokio::test]
+    async fn group_await_hold_and_release() {
+        let registry: &'static Registry = Box::leak(Box::default());
+        let member = |group: &str| {
+            let mut mon = monitor(ESCALATING);
+            mon.groups = vec![(group.to_string(), Duration::from_secs(60))];
+            mon.register_registry(registry);
+            mon
+        };
+        registry.update("fleet", MonitorStatus::default());
+
+        // failures are held until the group has evaluated them
+        let mut mon = member("fleet");
+        let page = recorder(&mut mon, "page");
+        run_status(&mut mon, 1, 1).await;
+        run_status(&mut mon, 2, 1).await;
+        assert_eq!(mon.level_index, 0);
+        assert!(calls(&page).is_empty());
+        let since = mon.failing_since.unwrap();
+        registry.update(
+            "fleet",
+            MonitorStatus {
+                last_run: since + 1,
+                ..Default::default()
+            },
+        );
+        run_status(&mut mon, 3, 1).await;
+        assert_eq!(mon.level_index, 1);
+        assert_eq!(calls(&page), ["report"]);
+
+        // a group that never evaluates holds for one group interval
+        registry.update("stalled", MonitorStatus::default());
+        let mut mon = member("stalled");
+        run_status(&mut mon, 1, 1).await;
+        assert_eq!(mon.level_index, 0);
+        run_status(&mut mon, 70, 1).await;
+        assert_eq!(mon.level_index, 1);
+
+        // a group without a published status holds nothing
+        let mut mon = member("missing");
+        run_status(&mut mon, 1, 1).await;
+        assert_eq!(mon.level_index, 1);
+    }
+
+    #[tokio::test]
+    async fn ack_hold_and_dependency_precedence() {
+        let registry: &'static Registry = Box::leak(Box::default());
+        let db = db::SynthDb::new(None).await.unwrap();
+        db.initialize_db().await.unwrap();
+        let db: &'static db::SynthDb = Box::leak(Box::new(db));
+        let mut mon = monitor(
+            r#"
+            name = "web"
+            interval = 1
+            depends_on = ["db"]
+            [target]
+            path = "true"
+            [[level]]
+            name = "info"
+            reporters = []
+            [[level]]
+            name = "alert"
+            reporters = ["page"]
+            [[level]]
+            name = "critical"
+            reporters = ["page"]
+            "#,
+        );
+        mon.register_registry(registry);
+        mon.register_db(db);
+        mon.register_incident_db(db);
+        let page = recorder(&mut mon, "page");
+        run_status(&mut mon, 1, 1).await;
+        assert_eq!(mon.level_index, 1);
+        let ack = Acknowledgement {
+            time: 0,
+            expires_at: None,
+            by: String::from("ops"),
+            comment: String::new(),
+        };
+        assert!(db
+            .acknowledge_incident(mon.incident_id.unwrap(), ack)
+            .await
+            .unwrap());
+
+        // an escalated dependency holds the monitor ahead of the ack
+        registry.update(
+            "db",
+            MonitorStatus {
+                level_index: 1,
+                ..Default::default()
+            },
+        );
+        run_status(&mut mon, 2, 1).await;
+        assert!(mon.held);
+        assert!(mon.acknowledged);
+        assert_eq!(mon.level_index, 1);
+
+        // once the dependency recovers the ack still holds escalation
+        registry.update("db", MonitorStatus::default());
+        run_status(&mut mon, 3, 1).await;
+        assert!(!mon.held);
+        assert_eq!(mon.level_index, 1);
+        assert_eq!(calls(&page), ["report"]);
+
+        // and escalation resumes when it's removed
+        db.unacknowledge_incident(mon.incident_id.unwrap())
+            .await
+            .unwrap();
+        run_status(&mut mon, 4, 1).await;
+        assert!(!mon.acknowledged);
+        assert_eq!(mon.level_index, 2);
+        assert_eq!(calls(&page), ["report", "report"]);
+    }
+
+    #[tokio::test]
+    async fn notify_mode_per_reporter() {
+        let mut mon = monitor(
+            r#"
+            name = "web"
+            interval = 1
+            [target]
+            path = "true"
+            [[level]]
+            name = "info"
+            errors_to_escalate = 10
+            reporter_notify = { chat = "on_change" }
+            reporters = ["stream", "chat"]
+            "#,
+        );
+        let stream = recorder(&mut mon, "stream");
+        let chat = recorder(&mut mon, "chat");
+        for (run, status) in [(1, 0), (2, 0), (3, 1), (4, 1), (5, 0)] {
+            run_status(&mut mon, run, status).await;
+        }
+        // every-run reporters are also cleared on each passing run
+        assert_eq!(
+            calls(&stream),
+            ["clear", "report", "clear", "report", "report", "report", "clear", "report"]
+        );
+        assert_eq!(calls(&chat), ["report", "report", "clear", "report"]);
+    }
+
+    #[tokio::test]
+    async fn batched_and_dropped_admission() {
+        let reporters: Vec<ReporterArgs> = ["chat", "page"]
+            .iter()
+            .map(|n| toml::from_str(&format!("name = \"{}\"\ntype = \"slack\"", n)).unwrap())
+            .collect();
+        let mut dispatcher = toml::from_str::<DispatchArgs>(
+            r#"
+            digest_window = "1m"
+            digest_threshold = 1
+            rate_limit = { page = 1 }
+            "#,
+        )
+        .unwrap()
+        .build(&reporters)
+        .unwrap();
+        let digest = Arc::new(Mutex::new(Vec::new()));
+        dispatcher.register_reporter("chat", Box::new(Recorder(digest.clone())));
+        let dispatcher: &'static Dispatcher = Box::leak(Box::new(dispatcher));
+        let escalating = |name: &str| {
+            let mut mon = monitor(&ESCALATING.replace("web", name));
+            mon.levels[1].reporters = vec![String::from("chat"), String::from("page")];
+            mon.register_dispatcher(dispatcher);
+            mon
+        };
+
+        // the first failure is sent to both reporters
+        let mut web = escalating("web");
+        let (web_chat, web_page) = (recorder(&mut web, "chat"), recorder(&mut web, "page"));
+        run_status(&mut web, 1, 1).await;
+        assert_eq!(calls(&web_chat), ["report"]);
+        assert_eq!(calls(&web_page), ["report"]);
+
+        // beyond the threshold chat is batched, and page is over its rate
+        // limit without digest support so it's dropped
+        let mut api = escalating("api");
+        let (api_chat, api_page) = (recorder(&mut api, "chat"), recorder(&mut api, "page"));
+        run_status(&mut api, 1, 1).await;
+        assert!(calls(&api_chat).is_empty());
+        assert!(calls(&api_page).is_empty());
+        assert!(calls(&digest).is_empty());
+
+        // the batched failure is tracked so it's cleared on recovery
+        assert!(api.notified.contains("chat"));
+        assert!(!api.notified.contains("page"));
+        run_status(&mut api, 2, 0).await;
+        assert_eq!(calls(&api_chat), ["clear"]);
+        assert!(api.notified.is_empty());
+    }
 }
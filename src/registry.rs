@@ -0,0 +1,61 @@
+//! Shared registry of monitor status.
+//!
+//! Each monitor runs isolated in its own task, so any behavior that
+//! depends on the state of other monitors (e.g. dependencies) goes
+//! through the registry, which each monitor updates after every run.
+//!
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::matcher::glob_match;
+
+/// The most recently published status of a monitor
+#[derive(Clone, Debug, Default)]
+pub struct MonitorStatus {
+    pub level_index: usize,
+    pub held: bool,
+    /// the last run of the monitor failed
+    pub failing: bool,
+    /// start time of the last run (ms), 0 before the first run
+    pub last_run: u64,
+}
+
+impl MonitorStatus {
+    /// escalated past the base level
+    pub fn escalated(&self) -> bool {
+        self.level_index > 0
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Registry {
+    monitors: RwLock<HashMap<String, MonitorStatus>>,
+}
+
+impl Registry {
+    pub fn update(&self, name: &str, status: MonitorStatus) {
+        self.monitors
+            .write()
+            .expect("monitor registry lock poisoned")
+            .insert(name.to_string(), status);
+    }
+
+    pub fn get(&self, name: &str) -> Option<MonitorStatus> {
+        self.monitors
+            .read()
+            .expect("monitor registry lock poisoned")
+            .get(name)
+            .cloned()
+    }
+
+    /// status of every monitor with a name matching the pattern
+    pub fn matching(&self, pattern: &str) -> Vec<(String, MonitorStatus)> {
+        self.monitors
+            .read()
+            .expect("monitor registry lock poisoned")
+            .iter()
+            .filter(|(name, _)| glob_match(pattern, name))
+            .map(|(name, s)| (name.clone(), s.clone()))
+            .collect()
+    }
+}
@@ -0,0 +1,116 @@
+//! Remediation commands attached to a level, run once when a monitor
+//! escalates into that level to attempt an automatic fix such as
+//! restarting a service or clearing a cache.
+//!
+//! The triggering MonitorResult is passed to the command as environment
+//! variables prefixed with SYNTHEHOL_ (e.g. SYNTHEHOL_STATUS).
+//!
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::Instant;
+use tracing::instrument;
+
+use crate::config;
+use crate::monitor::MonitorResult;
+
+#[derive(Clone, Debug)]
+pub struct Remediation {
+    pub path: String,
+    args: Option<Vec<String>>,
+    env: Option<Vec<(String, String)>>,
+    timeout: Duration,
+    /// run the monitor target again straight after remediating
+    pub rerun_target: bool,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct RemediationArgs {
+    path: String,
+    args: Option<Vec<String>>,
+    env: Option<Vec<(String, String)>>,
+    #[serde(default, deserialize_with = "config::deserialize_opt_duration")]
+    timeout: Option<Duration>,
+    rerun_target: Option<bool>,
+}
+
+#[derive(Debug)]
+pub struct RemediationOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub duration: u64,
+    /// None if the command timed out or was killed by a signal
+    pub status: Option<i32>,
+}
+
+impl RemediationArgs {
+    pub fn build(self) -> Result<Remediation, String> {
+        let timeout = self.timeout.unwrap_or(Duration::from_secs(30));
+        if timeout.is_zero() {
+            return Err(String::from(
+                "remediation timeout must be greater than zero",
+            ));
+        }
+        Ok(Remediation {
+            path: self.path,
+            args: self.args,
+            env: self.env,
+            timeout,
+            rerun_target: self.rerun_target.unwrap_or(false),
+        })
+    }
+}
+
+impl Remediation {
+    /// Run the remediation command for a result, killing it if it
+    /// outlives the timeout
+    #[instrument(level=tracing::Level::DEBUG, skip(res))]
+    pub async fn run(&self, res: &MonitorResult) -> Result<RemediationOutput, String> {
+        let start = Instant::now();
+        let mut cmd = Command::new(&self.path);
+        cmd.envs(result_env(res)).kill_on_drop(true);
+        if let Some(env) = self.env.clone() {
+            cmd.envs(env);
+        }
+        if let Some(args) = self.args.clone() {
+            cmd.args(args);
+        }
+        let output = tokio::time::timeout(self.timeout, cmd.output()).await;
+        let duration = (Instant::now() - start).as_micros() as u64;
+        match output {
+            Ok(output) => {
+                let output = output.map_err(|e| format!("failed to run remediation ({0})", e))?;
+                Ok(RemediationOutput {
+                    stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                    duration,
+                    status: output.status.code(),
+                })
+            }
+            Err(_) => Ok(RemediationOutput {
+                stdout: String::new(),
+                stderr: format!("timed out after {:?}", self.timeout),
+                duration,
+                status: None,
+            }),
+        }
+    }
+}
+
+/// the fields of a result as SYNTHEHOL_<FIELD> environment variables
+fn result_env(res: &MonitorResult) -> Vec<(String, String)> {
+    let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(res) else {
+        return Vec::new();
+    };
+    fields
+        .into_iter()
+        .map(|(k, v)| {
+            let v = match v {
+                serde_json::Value::String(s) => s,
+                serde_json::Value::Null => String::new(),
+                v => v.to_string(),
+            };
+            (format!("SYNTHEHOL_{}", k.to_uppercase()), v)
+        })
+        .collect()
+}
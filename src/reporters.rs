@@ -4,8 +4,15 @@ pub mod slack;
 pub mod splunk;
 
 use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::BTreeMap;
 
-use crate::monitor::MonitorResult;
+use crate::dispatch::Digest;
+use crate::monitor::{MonitorResult, Transition};
+use pagerduty::PagerdutyReporterFactory;
+use postgresql::PostgresqlReporterFactory;
+use slack::SlackReporterFactory;
+use splunk::SplunkReporterFactory;
 
 /// Reporters have an async report() function that handles a monitor result
 /// taking care of any formatting and delivery required
@@ -13,6 +20,142 @@ use crate::monitor::MonitorResult;
 pub trait Reporter {
     async fn report(&mut self, _: &MonitorResult);
     async fn clear(&mut self, _: &MonitorResult);
+    /// Called once when a monitor starts flapping, normal reports and
+    /// clears are suppressed until it stops and clear is called
+    async fn flap(&mut self, res: &MonitorResult) {
+        self.report(res).await
+    }
+    /// Called once when the open incident is acknowledged, for
+    /// reporters that have already been sent a report in the incident
+    async fn acknowledge(&mut self, _: &MonitorResult) {}
+    /// Called when the monitor changes level, for reporters that keep
+    /// a record of level history
+    async fn transition(&mut self, _: &Transition) {}
+    /// Called by the dispatcher with the notifications held during a
+    /// digest window, for reporters registered to receive digests
+    async fn digest(&mut self, _: &Digest) {}
+    /// Reporters that notify people rather than record results, sinks
+    /// are still sent the results of a monitor holding its escalation
+    fn alerts(&self) -> bool {
+        true
+    }
     fn get_state(&self) -> Option<Vec<u8>>;
     fn load_state(&mut self, _: Vec<u8>);
 }
+
+/// A named reporter instance, referenced by name in monitor levels so
+/// several reporters of the same type can be configured
+#[derive(Clone, Deserialize, Debug)]
+pub struct ReporterArgs {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// the reporter type's own config, passed to its factory
+    #[serde(flatten)]
+    pub args: toml::Table,
+}
+
+impl ReporterArgs {
+    pub fn new(name: &str, kind: &str, args: toml::Table) -> Self {
+        ReporterArgs {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            args,
+        }
+    }
+}
+
+/// Factories build reporters of a single type from their config
+#[async_trait]
+pub trait ReporterFactory {
+    async fn build(
+        &self,
+        args: toml::Value,
+    ) -> Result<Box<dyn Reporter + Send + Sync + 'static>, String>;
+    /// fields that may be overridden per monitor or level
+    fn overridable(&self) -> &'static [&'static str] {
+        &[]
+    }
+    /// reporters of this type can be sent digests by the dispatcher
+    fn supports_digest(&self) -> bool {
+        false
+    }
+}
+
+/// Reporter factories keyed by type name, new reporters only need
+/// registering here to be configurable
+pub struct ReporterRegistry {
+    factories: BTreeMap<String, Box<dyn ReporterFactory + Send + Sync>>,
+}
+
+impl Default for ReporterRegistry {
+    fn default() -> Self {
+        let mut r = ReporterRegistry {
+            factories: BTreeMap::new(),
+        };
+        r.register("slack", Box::new(SlackReporterFactory));
+        r.register("splunk", Box::new(SplunkReporterFactory));
+        r.register("pagerduty", Box::new(PagerdutyReporterFactory));
+        r.register("postgresql", Box::new(PostgresqlReporterFactory));
+        r
+    }
+}
+
+impl ReporterRegistry {
+    pub fn register(&mut self, kind: &str, factory: Box<dyn ReporterFactory + Send + Sync>) {
+        self.factories.insert(kind.to_lowercase(), factory);
+    }
+
+    fn factory(&self, r: &ReporterArgs) -> Result<&(dyn ReporterFactory + Send + Sync), String> {
+        match self.factories.get(&r.kind.to_lowercase()) {
+            Some(f) => Ok(f.as_ref()),
+            None => Err(format!(
+                "reporter {} has unknown type {} (expected one of: {})",
+                r.name,
+                r.kind,
+                self.factories
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    /// check the reporter's type is registered
+    pub fn validate(&self, r: &ReporterArgs) -> Result<(), String> {
+        self.factory(r).map(|_| ())
+    }
+
+    pub fn supports_digest(&self, r: &ReporterArgs) -> bool {
+        self.factory(r).is_ok_and(|f| f.supports_digest())
+    }
+
+    /// build a new instance of the reporter
+    pub async fn build(
+        &self,
+        r: &ReporterArgs,
+    ) -> Result<Box<dyn Reporter + Send + Sync + 'static>, String> {
+        self.factory(r)?
+            .build(toml::Value::Table(r.args.clone()))
+            .await
+            .map_err(|e| format!("reporter {} {}", r.name, e))
+    }
+
+    /// Merge overridden fields over the reporter's args, only fields
+    /// that can differ between monitors may be overridden
+    pub fn merge(&self, r: &ReporterArgs, overrides: &toml::Table) -> Result<ReporterArgs, String> {
+        let allowed = self.factory(r)?.overridable();
+        let mut args = r.args.clone();
+        for (k, v) in overrides {
+            if !allowed.contains(&k.as_str()) {
+                return Err(format!(
+                    "reporter {} field {} can't be overridden",
+                    r.name, k
+                ));
+            }
+            args.insert(k.clone(), v.clone());
+        }
+        Ok(ReporterArgs::new(&r.name, &r.kind, args))
+    }
+}
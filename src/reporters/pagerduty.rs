@@ -1,5 +1,5 @@
 use crate::monitor::MonitorResult;
-use crate::reporters::Reporter;
+use crate::reporters::{Reporter, ReporterFactory};
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde::Serialize;
@@ -48,7 +48,7 @@ enum Severity {
 #[serde(rename_all = "lowercase")]
 enum Action {
     Trigger,
-    // Acknowledge,
+    Acknowledge,
     Resolve,
 }
 
@@ -65,6 +65,13 @@ struct PagerdutyPayload {
     group: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     class: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    custom_details: Option<PagerdutyDetails>,
+}
+
+#[derive(Serialize, Debug)]
+struct PagerdutyDetails {
+    incident_id: i64,
 }
 
 #[derive(Serialize, Debug)]
@@ -107,6 +114,32 @@ impl PagerdutyReporterArgs {
     }
 }
 
+pub struct PagerdutyReporterFactory;
+
+#[async_trait]
+impl ReporterFactory for PagerdutyReporterFactory {
+    async fn build(
+        &self,
+        args: toml::Value,
+    ) -> Result<Box<dyn Reporter + Send + Sync + 'static>, String> {
+        let r: PagerdutyReporterArgs = args
+            .try_into()
+            .map_err(|e: toml::de::Error| e.message().to_string())?;
+        Ok(Box::new(r.build().map_err(|e| e.to_string())?))
+    }
+
+    fn overridable(&self) -> &'static [&'static str] {
+        &[
+            "routing_key",
+            "component",
+            "group",
+            "class",
+            "source",
+            "report_tmpl",
+        ]
+    }
+}
+
 impl PagerdutyReporter {
     fn initialize(&mut self) -> Result<(), upon::Error> {
         self.renderer
@@ -128,6 +161,9 @@ impl PagerdutyReporter {
             component: self.component.clone(),
             group: self.group.clone(),
             class: self.class.clone(),
+            custom_details: output
+                .incident_id
+                .map(|incident_id| PagerdutyDetails { incident_id }),
         });
         Ok(PagerdutyMsg {
             payload,
@@ -184,6 +220,24 @@ impl Reporter for PagerdutyReporter {
         }
     }
 
+    #[instrument]
+    async fn acknowledge(&mut self, output: &MonitorResult) {
+        if self.dedup_key.is_none() {
+            return;
+        }
+        let output = self.format(output);
+        match output {
+            Ok(mut output) => {
+                output.event_action = Action::Acknowledge;
+                match self.send(&output).await {
+                    Ok(_) => debug!("pagerduty incident acknowledged successfully"),
+                    Err(e) => error!(e),
+                }
+            }
+            Err(e) => warn!(e),
+        }
+    }
+
     #[instrument]
     async fn clear(&mut self, output: &MonitorResult) {
         let output = self.format(output);
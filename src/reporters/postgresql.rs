@@ -1,5 +1,5 @@
-use crate::monitor::MonitorResult;
-use crate::reporters::Reporter;
+use crate::monitor::{MonitorResult, Transition};
+use crate::reporters::{Reporter, ReporterFactory};
 use async_trait::async_trait;
 use serde::Deserialize;
 use sqlx::postgres::PgPoolOptions;
@@ -49,6 +49,21 @@ impl PostgresqlReporterArgs {
     }
 }
 
+pub struct PostgresqlReporterFactory;
+
+#[async_trait]
+impl ReporterFactory for PostgresqlReporterFactory {
+    async fn build(
+        &self,
+        args: toml::Value,
+    ) -> Result<Box<dyn Reporter + Send + Sync + 'static>, String> {
+        let r: PostgresqlReporterArgs = args
+            .try_into()
+            .map_err(|e: toml::de::Error| e.message().to_string())?;
+        Ok(Box::new(r.build().await.map_err(|e| e.to_string())?))
+    }
+}
+
 impl PostgresqlReporter {
     #[instrument]
     async fn initialize_db(&mut self) -> Result<(), sqlx::Error> {
@@ -72,10 +87,15 @@ impl PostgresqlReporter {
         .await?;
         debug!("postgresql synthehol schema created/confirmed");
 
+        // pool needs to be set before creating tables
+        self.pg_db = PgDb { pool: Some(pool) };
+
         self.create_results_table().await?;
         debug!("Monitor results table creation succeeded");
 
-        self.pg_db = PgDb { pool: Some(pool) };
+        self.create_transitions_table().await?;
+        debug!("Monitor transitions table creation succeeded");
+
         Ok(())
     }
 
@@ -98,7 +118,9 @@ impl PostgresqlReporter {
                     stdout      TEXT        NOT NULL,
                     stderr      TEXT        NOT NULL,
                     duration    BIGINT      NOT NULL,
-                    status      INTEGER     NOT NULL
+                    queue_time  BIGINT      NOT NULL DEFAULT 0,
+                    status      INTEGER     NOT NULL,
+                    incident_id BIGINT
                 );
 
             ",
@@ -106,6 +128,43 @@ impl PostgresqlReporter {
             .execute(p)
             .await?;
             debug!("postgresql monitor table created/confirmed ({r:?})");
+
+            // columns added after the initial release
+            sqlx::query(
+                "
+                ALTER TABLE synthehol.monitor_results
+                    ADD COLUMN IF NOT EXISTS queue_time BIGINT NOT NULL DEFAULT 0,
+                    ADD COLUMN IF NOT EXISTS incident_id BIGINT;
+            ",
+            )
+            .execute(p)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+impl PostgresqlReporter {
+    #[instrument]
+    async fn create_transitions_table(&self) -> Result<(), sqlx::Error> {
+        // create transitions table if it doesn't exist
+        if let Some(p) = &self.pg_db.pool {
+            let r = sqlx::query(
+                "
+                CREATE TABLE IF NOT EXISTS synthehol.monitor_transitions (
+                    id          BIGSERIAL PRIMARY KEY,
+                    name        TEXT        NOT NULL,
+                    time        TIMESTAMP   NOT NULL,
+                    from_level  TEXT        NOT NULL,
+                    to_level    TEXT        NOT NULL,
+                    reason      TEXT        NOT NULL,
+                    result_id   BIGINT
+                );
+            ",
+            )
+            .execute(p)
+            .await?;
+            debug!("postgresql transitions table created/confirmed ({r:?})");
         }
         Ok(())
     }
@@ -117,6 +176,7 @@ impl Reporter for PostgresqlReporter {
     async fn report(&mut self, output: &MonitorResult) {
         let start_time: i64 = output.start_time.try_into().expect("invalid start time");
         let duration: i64 = output.duration.try_into().expect("invalid duration");
+        let queue_time: i64 = output.queue_time.try_into().expect("invalid queue time");
 
         if let Some(p) = &self.pg_db.pool {
             let r = sqlx::query(
@@ -130,10 +190,12 @@ impl Reporter for PostgresqlReporter {
                     stdout,
                     stderr,
                     duration,
-                    status
+                    queue_time,
+                    status,
+                    incident_id
                 )
                 VALUES (
-                    $1, $2, to_timestamp($3 / 1000.0), $4, $5, $6, $7, $8, $9
+                    $1, $2, to_timestamp($3 / 1000.0), $4, $5, $6, $7, $8, $9, $10, $11
                 );
                 ",
             )
@@ -145,7 +207,9 @@ impl Reporter for PostgresqlReporter {
             .bind(&output.stdout)
             .bind(&output.stderr)
             .bind(duration)
+            .bind(queue_time)
             .bind(output.status)
+            .bind(output.incident_id)
             .execute(p)
             .await;
 
@@ -164,6 +228,50 @@ impl Reporter for PostgresqlReporter {
         // nothing to do here
     }
 
+    #[instrument]
+    async fn transition(&mut self, t: &Transition) {
+        let time: i64 = t.time.try_into().expect("invalid transition time");
+
+        if let Some(p) = &self.pg_db.pool {
+            let r = sqlx::query(
+                "
+                INSERT INTO synthehol.monitor_transitions (
+                    name,
+                    time,
+                    from_level,
+                    to_level,
+                    reason,
+                    result_id
+                )
+                VALUES (
+                    $1, to_timestamp($2 / 1000.0), $3, $4, $5, $6
+                );
+                ",
+            )
+            .bind(&t.monitor)
+            .bind(time)
+            .bind(&t.from_level)
+            .bind(&t.to_level)
+            .bind(&t.reason)
+            .bind(t.result_id)
+            .execute(p)
+            .await;
+
+            match r {
+                Ok(r) => {
+                    debug!("postgresql transition successfully processed ({r:?})");
+                }
+                Err(e) => {
+                    error!("postgresql transition failed to process ({e})");
+                }
+            }
+        }
+    }
+
+    fn alerts(&self) -> bool {
+        false
+    }
+
     fn get_state(&self) -> Option<Vec<u8>> {
         // nothing to do here
         None
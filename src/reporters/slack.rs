@@ -1,4 +1,8 @@
-use crate::{monitor::MonitorResult, reporters::Reporter};
+use crate::{
+    dispatch::Digest,
+    monitor::MonitorResult,
+    reporters::{Reporter, ReporterFactory},
+};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, instrument, warn};
@@ -9,6 +13,9 @@ pub struct SlackReporter {
     webhook_url: String,
     report_tmpl: String,
     clear_tmpl: String,
+    flap_tmpl: String,
+    ack_tmpl: String,
+    digest_tmpl: String,
 }
 
 #[derive(Clone, Deserialize, Debug)]
@@ -16,6 +23,9 @@ pub struct SlackReporterArgs {
     webhook_url: String,
     report_tmpl: Option<String>,
     clear_tmpl: Option<String>,
+    flap_tmpl: Option<String>,
+    ack_tmpl: Option<String>,
+    digest_tmpl: Option<String>,
 }
 
 #[derive(Serialize, Debug)]
@@ -42,27 +52,67 @@ impl SlackReporterArgs {
             webhook_url: self.webhook_url,
             report_tmpl: self.report_tmpl.unwrap_or(DEF_REPORT_TEMPLATE.to_owned()),
             clear_tmpl: self.clear_tmpl.unwrap_or(DEF_CLEAR_TEMPLATE.to_owned()),
+            flap_tmpl: self.flap_tmpl.unwrap_or(DEF_FLAP_TEMPLATE.to_owned()),
+            ack_tmpl: self.ack_tmpl.unwrap_or(DEF_ACK_TEMPLATE.to_owned()),
+            digest_tmpl: self.digest_tmpl.unwrap_or(DEF_DIGEST_TEMPLATE.to_owned()),
         };
         r.initialize()?;
         Ok(r)
     }
 }
 
+pub struct SlackReporterFactory;
+
+#[async_trait]
+impl ReporterFactory for SlackReporterFactory {
+    async fn build(
+        &self,
+        args: toml::Value,
+    ) -> Result<Box<dyn Reporter + Send + Sync + 'static>, String> {
+        let r: SlackReporterArgs = args
+            .try_into()
+            .map_err(|e: toml::de::Error| e.message().to_string())?;
+        Ok(Box::new(r.build().map_err(|e| e.to_string())?))
+    }
+
+    fn overridable(&self) -> &'static [&'static str] {
+        &[
+            "webhook_url",
+            "report_tmpl",
+            "clear_tmpl",
+            "flap_tmpl",
+            "ack_tmpl",
+        ]
+    }
+
+    fn supports_digest(&self) -> bool {
+        true
+    }
+}
+
 impl SlackReporter {
     fn initialize(&mut self) -> Result<(), upon::Error> {
         self.renderer
             .add_template("report", self.report_tmpl.clone())?;
         self.renderer
             .add_template("clear", self.clear_tmpl.clone())?;
+        self.renderer.add_template("flap", self.flap_tmpl.clone())?;
+        self.renderer.add_template("ack", self.ack_tmpl.clone())?;
+        self.renderer
+            .add_template("digest", self.digest_tmpl.clone())?;
         Ok(())
     }
 
     #[instrument]
     fn format(&self, template: &str, output: &MonitorResult) -> Result<SlackMessage, String> {
+        self.render(template, upon::value![res: output])
+    }
+
+    fn render(&self, template: &str, ctx: upon::Value) -> Result<SlackMessage, String> {
         let body = self
             .renderer
             .template(template)
-            .render(upon::value![res: output])
+            .render(ctx)
             .to_string()
             .map_err(|e| format!("error rendering slack template {0}", e))?;
         let out = SlackMessage {
@@ -123,6 +173,46 @@ impl Reporter for SlackReporter {
         }
     }
 
+    #[instrument]
+    async fn flap(&mut self, output: &MonitorResult) {
+        let slack_content = self.format("flap", output);
+        match slack_content {
+            Ok(slack_content) => {
+                debug!("slack flapping notification for monitor {}", output.name);
+                self.send(&slack_content).await;
+            }
+            Err(e) => {
+                warn!(e);
+            }
+        }
+    }
+
+    #[instrument]
+    async fn digest(&mut self, digest: &Digest) {
+        match self.render("digest", upon::value![digest: digest]) {
+            Ok(slack_content) => {
+                self.send(&slack_content).await;
+            }
+            Err(e) => {
+                warn!(e);
+            }
+        }
+    }
+
+    #[instrument]
+    async fn acknowledge(&mut self, output: &MonitorResult) {
+        let slack_content = self.format("ack", output);
+        match slack_content {
+            Ok(slack_content) => {
+                debug!("slack acknowledgement for monitor {}", output.name);
+                self.send(&slack_content).await;
+            }
+            Err(e) => {
+                warn!(e);
+            }
+        }
+    }
+
     fn get_state(&self) -> Option<Vec<u8>> {
         None
     }
@@ -139,3 +229,11 @@ const DEF_REPORT_TEMPLATE: &str = "*Monitor: {{res.name}} [level: {{res.level_na
 *duration:* {{ res.duration }} μs";
 
 const DEF_CLEAR_TEMPLATE: &str = "*Monitor: {{res.name}} returned to baseline*";
+
+const DEF_FLAP_TEMPLATE: &str = "*Monitor: {{res.name}} is flapping [level: {{res.level_name}}]*, notifications suppressed until it stabilizes";
+
+const DEF_ACK_TEMPLATE: &str = "*Monitor: {{res.name}} incident {{res.incident_id}} acknowledged [level: {{res.level_name}}]*, escalation held";
+
+const DEF_DIGEST_TEMPLATE: &str = "*{{digest.monitors}} monitors sent {{digest.count}} held notifications in the last {{digest.window}}*
+{% for e in digest.entries %}{{ e.monitor }} [level: {{ e.level_name }}, status: {{ e.status }}]
+{% endfor %}";
@@ -1,5 +1,5 @@
 use crate::monitor::MonitorResult;
-use crate::reporters::Reporter;
+use crate::reporters::{Reporter, ReporterFactory};
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde::Serialize;
@@ -38,7 +38,10 @@ struct SplunkEvent {
     stdout: String,
     stderr: String,
     duration: u64,
+    queue_time: u64,
     status: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    incident_id: Option<i64>,
 }
 
 impl SplunkReporterArgs {
@@ -51,6 +54,25 @@ impl SplunkReporterArgs {
     }
 }
 
+pub struct SplunkReporterFactory;
+
+#[async_trait]
+impl ReporterFactory for SplunkReporterFactory {
+    async fn build(
+        &self,
+        args: toml::Value,
+    ) -> Result<Box<dyn Reporter + Send + Sync + 'static>, String> {
+        let r: SplunkReporterArgs = args
+            .try_into()
+            .map_err(|e: toml::de::Error| e.message().to_string())?;
+        Ok(Box::new(r.build()))
+    }
+
+    fn overridable(&self) -> &'static [&'static str] {
+        &["index"]
+    }
+}
+
 impl SplunkReporter {
     #[instrument]
     fn format(&self, output: &MonitorResult) -> SplunkMsg {
@@ -62,7 +84,9 @@ impl SplunkReporter {
             stdout: output.stdout.clone(),
             stderr: output.stderr.clone(),
             duration: output.duration,
+            queue_time: output.queue_time,
             status: output.status,
+            incident_id: output.incident_id,
         };
         SplunkMsg {
             source: String::from("Synthehol"),
@@ -97,6 +121,10 @@ impl Reporter for SplunkReporter {
         return;
     }
 
+    fn alerts(&self) -> bool {
+        false
+    }
+
     fn get_state(&self) -> Option<Vec<u8>> {
         // Vec::new()
         None
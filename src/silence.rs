@@ -0,0 +1,272 @@
+//! Silences suppress reporting for matching monitors during planned
+//! maintenance. Targets still run and results are still recorded while
+//! a monitor is silenced, only the dispatch of reports is skipped.
+//!
+//! Silences are either recurring windows defined in the configuration,
+//! or one-off silences created at runtime with a start and end time
+//! that are persisted in the sqlite database.
+//!
+use serde::Deserialize;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error};
+
+use crate::db::SynthDb;
+use crate::matcher::MonitorMatcher;
+
+/// how often the runtime silences are reloaded from the database
+const SILENCE_REFRESH: Duration = Duration::from_secs(10);
+
+/// A one-off silence, created at runtime and stored in the database
+#[derive(Clone, Debug)]
+pub struct Silence {
+    pub id: i64,
+    pub matcher: MonitorMatcher,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub creator: String,
+    pub comment: String,
+}
+
+impl Silence {
+    pub fn is_active(&self, now: u64) -> bool {
+        self.start_time <= now && now < self.end_time
+    }
+}
+
+/// Runtime silences that haven't ended, shared by all monitors and
+/// reloaded periodically so silences added from the cli are picked up
+/// without each monitor querying the database on every run
+#[derive(Debug, Default)]
+pub struct SilenceCache {
+    silences: RwLock<Vec<Silence>>,
+}
+
+impl SilenceCache {
+    pub async fn refresh(&self, db: &SynthDb) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("System time error")
+            .as_millis() as u64;
+        match db.get_silences(Some(now)).await {
+            Ok(silences) => {
+                debug!("loaded {} silences", silences.len());
+                *self.silences.write().expect("silence cache lock poisoned") = silences;
+            }
+            Err(e) => error!("failed to load silences ({})", e),
+        }
+    }
+
+    /// reload the silences on a fixed interval until cancelled
+    pub async fn run(&self, db: &SynthDb, cancel: CancellationToken) {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(SILENCE_REFRESH) => self.refresh(db).await,
+                _ = cancel.cancelled() => return,
+            }
+        }
+    }
+
+    /// an active silence matching the monitor
+    pub fn matching(&self, name: &str, tags: &[String], now: u64) -> Option<Silence> {
+        self.silences
+            .read()
+            .expect("silence cache lock poisoned")
+            .iter()
+            .find(|s| s.is_active(now) && s.matcher.matches(name, tags))
+            .cloned()
+    }
+}
+
+/// A recurring silence window, active between the start and end time of
+/// day (UTC) on the configured days of the week
+#[derive(Clone, Debug)]
+pub struct SilenceWindow {
+    pub matcher: MonitorMatcher,
+    days: Vec<u64>,
+    start: u64,
+    end: u64,
+    pub comment: String,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct SilenceWindowArgs {
+    monitor: Option<String>,
+    tag: Option<String>,
+    days: Option<Vec<String>>,
+    start: String,
+    end: String,
+    comment: Option<String>,
+}
+
+const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+impl SilenceWindowArgs {
+    pub fn build(self) -> Result<SilenceWindow, String> {
+        let matcher = MonitorMatcher {
+            monitor: self.monitor,
+            tag: self.tag,
+        };
+        if matcher.is_empty() {
+            return Err(String::from("silence requires a monitor or tag matcher"));
+        }
+        let mut days = Vec::new();
+        for d in self.days.unwrap_or_default() {
+            let d_l = d.to_lowercase();
+            match DAYS.iter().position(|x| d_l.starts_with(x)) {
+                Some(i) => days.push(i as u64),
+                None => return Err(format!("invalid silence day ({})", d)),
+            }
+        }
+        Ok(SilenceWindow {
+            matcher,
+            days,
+            start: parse_time_of_day(&self.start)?,
+            end: parse_time_of_day(&self.end)?,
+            comment: self.comment.unwrap_or_default(),
+        })
+    }
+}
+
+impl SilenceWindow {
+    /// check if the window is active at the given time (ms since epoch),
+    /// windows with an end before their start run past midnight and
+    /// are matched against the day they started on
+    pub fn is_active(&self, now: u64) -> bool {
+        let secs = now / 1000;
+        let minute = (secs % 86400) / 60;
+        // 1970-01-01 was a thursday
+        let weekday = (secs / 86400 + 3) % 7;
+        if self.start <= self.end {
+            self.on_day(weekday) && self.start <= minute && minute < self.end
+        } else {
+            (self.on_day(weekday) && minute >= self.start)
+                || (self.on_day((weekday + 6) % 7) && minute < self.end)
+        }
+    }
+
+    fn on_day(&self, weekday: u64) -> bool {
+        self.days.is_empty() || self.days.contains(&weekday)
+    }
+}
+
+/// parse "HH:MM" into minutes past midnight
+fn parse_time_of_day(t: &str) -> Result<u64, String> {
+    let err = || format!("invalid silence time, expected HH:MM ({})", t);
+    let (h, m) = t.split_once(':').ok_or_else(err)?;
+    let h: u64 = h.trim().parse().map_err(|_| err())?;
+    let m: u64 = m.trim().parse().map_err(|_| err())?;
+    if h > 24 || m > 59 || (h == 24 && m != 0) {
+        return Err(err());
+    }
+    Ok(h * 60 + m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01 00:00 UTC, a monday
+    const MONDAY: u64 = 19723 * 86_400_000;
+
+    fn at(day: u64, hour: u64, minute: u64) -> u64 {
+        MONDAY + day * 86_400_000 + (hour * 60 + minute) * 60_000
+    }
+
+    fn window(days: &[&str], start: &str, end: &str) -> SilenceWindow {
+        SilenceWindowArgs {
+            monitor: Some(String::from("*")),
+            tag: None,
+            days: Some(days.iter().map(|d| d.to_string()).collect()),
+            start: start.to_string(),
+            end: end.to_string(),
+            comment: None,
+        }
+        .build()
+        .unwrap()
+    }
+
+    #[test]
+    fn window_within_day() {
+        let w = window(&[], "02:00", "04:00");
+        assert!(!w.is_active(at(0, 1, 59)));
+        assert!(w.is_active(at(0, 2, 0)));
+        assert!(w.is_active(at(3, 3, 59)));
+        assert!(!w.is_active(at(0, 4, 0)));
+    }
+
+    #[test]
+    fn window_days_of_week() {
+        let w = window(&["sat", "Sunday"], "00:00", "24:00");
+        assert!(!w.is_active(at(0, 12, 0)));
+        assert!(!w.is_active(at(4, 23, 59)));
+        assert!(w.is_active(at(5, 0, 0)));
+        assert!(w.is_active(at(6, 23, 59)));
+        assert!(!w.is_active(at(7, 0, 0)));
+    }
+
+    #[test]
+    fn window_wraps_midnight() {
+        // friday night into saturday morning
+        let w = window(&["fri"], "22:00", "02:00");
+        assert!(!w.is_active(at(4, 21, 59)));
+        assert!(w.is_active(at(4, 22, 0)));
+        assert!(w.is_active(at(5, 1, 59)));
+        assert!(!w.is_active(at(5, 2, 0)));
+        assert!(!w.is_active(at(5, 22, 0)));
+        // early on friday belongs to a thursday window
+        assert!(!w.is_active(at(4, 1, 0)));
+    }
+
+    #[test]
+    fn window_wraps_week() {
+        // sunday night into monday morning
+        let w = window(&["sun"], "23:00", "01:00");
+        assert!(w.is_active(at(6, 23, 30)));
+        assert!(w.is_active(at(7, 0, 30)));
+        assert!(!w.is_active(at(7, 1, 0)));
+        assert!(!w.is_active(at(7, 23, 30)));
+    }
+
+    #[test]
+    fn window_invalid_day() {
+        let args = SilenceWindowArgs {
+            monitor: None,
+            tag: Some(String::from("prod")),
+            days: Some(vec![String::from("funday")]),
+            start: String::from("00:00"),
+            end: String::from("01:00"),
+            comment: None,
+        };
+        assert!(args.build().is_err());
+    }
+
+    #[test]
+    fn window_requires_matcher() {
+        let args = SilenceWindowArgs {
+            monitor: None,
+            tag: None,
+            days: None,
+            start: String::from("00:00"),
+            end: String::from("01:00"),
+            comment: None,
+        };
+        assert!(args.build().is_err());
+    }
+
+    #[test]
+    fn time_of_day() {
+        assert_eq!(parse_time_of_day("00:00"), Ok(0));
+        assert_eq!(parse_time_of_day("02:30"), Ok(150));
+        assert_eq!(parse_time_of_day(" 9:05 "), Ok(545));
+        assert_eq!(parse_time_of_day("23:59"), Ok(1439));
+        assert_eq!(parse_time_of_day("24:00"), Ok(1440));
+        assert!(parse_time_of_day("24:01").is_err());
+        assert!(parse_time_of_day("25:00").is_err());
+        assert!(parse_time_of_day("12:60").is_err());
+        assert!(parse_time_of_day("1200").is_err());
+        assert!(parse_time_of_day("ab:cd").is_err());
+        assert!(parse_time_of_day("").is_err());
+    }
+}
@@ -7,7 +7,7 @@ use tracing::instrument;
 /// some output and a 0 result code indicating success, and >0 for failure.
 /// Command-line arguments are provided by a vec of strings, and environment
 /// variables by a vec of (String, String) tuples
-#[derive(Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Target {
     pub path: String,
     pub args: Option<Vec<String>>,